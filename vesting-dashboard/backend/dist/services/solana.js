@@ -7,6 +7,15 @@ const contracts_1 = require("../config/contracts");
 const validation_1 = require("../utils/validation");
 const logger_1 = require("../utils/logger");
 const errorHandler_1 = require("../utils/errorHandler");
+// Versioned layout written by the program since the Migrate instruction
+// (first byte = 2): fixed header, then 59-byte recipient entries. Accounts
+// still in the original 641-byte layout use the sequential parser.
+const VESTING_LAYOUT_VERSION = 2;
+const VESTING_HEADER_LEN = 1198;
+const VESTING_RECIPIENT_LEN = 59;
+const VESTING_SCHEDULES_OFFSET = 271;
+const VESTING_SCHEDULE_LEN = 187;
+const VESTING_UNLOCK_POINT_LEN = 10;
 class SolanaService {
     constructor() {
         this.config = (0, contracts_1.getContractConfig)().solana;
@@ -63,6 +72,9 @@ class SolanaService {
     }
     parseVestingAccount(data) {
         try {
+            if (data[0] === VESTING_LAYOUT_VERSION) {
+                return this.parseVersionedVestingAccount(data);
+            }
             // ✅ UPDATED: Expected size with basis points (u16 instead of u8) and new fields
             if (data.length < 640) { // Updated minimum size
                 throw new Error(`Invalid account data length: ${data.length}, expected at least 640`);
@@ -140,6 +152,71 @@ class SolanaService {
             return null;
         }
     }
+    parseVersionedVestingAccount(data) {
+        if (data.length < VESTING_HEADER_LEN) {
+            throw new Error(`Invalid account data length: ${data.length}, expected at least ${VESTING_HEADER_LEN}`);
+        }
+        // Schedule 0 also fills the single-schedule fields; recipients point at
+        // their own schedule through scheduleIndex
+        const schedule = VESTING_SCHEDULES_OFFSET;
+        const schedules = [];
+        for (let i = 0; i < data[270]; i++) {
+            schedules.push(this.parseVersionedSchedule(data, VESTING_SCHEDULES_OFFSET + i * VESTING_SCHEDULE_LEN));
+        }
+        const recipientCount = data[114];
+        const recipients = [];
+        for (let i = 0; i < recipientCount; i++) {
+            const offset = VESTING_HEADER_LEN + i * VESTING_RECIPIENT_LEN;
+            if (offset + VESTING_RECIPIENT_LEN > data.length) {
+                logger_1.logger.warn(`Not enough data for recipient ${i}, stopping parsing`);
+                break;
+            }
+            const basisPoints = data.readUInt16LE(offset + 32);
+            if (basisPoints > 0) {
+                recipients.push({
+                    wallet: new web3_js_1.PublicKey(data.slice(offset, offset + 32)).toString(),
+                    basisPoints,
+                    claimedAmount: this.readUint64LE(data, offset + 34).toString(),
+                    lastClaimTime: Number(this.readInt64LE(data, offset + 42)),
+                    revokedAt: Number(this.readInt64LE(data, offset + 50)),
+                    scheduleIndex: data[offset + 58]
+                });
+            }
+        }
+        return {
+            isInitialized: data[1] !== 0,
+            initializer: new web3_js_1.PublicKey(data.slice(2, 34)).toString(),
+            mint: new web3_js_1.PublicKey(data.slice(34, 66)).toString(),
+            vault: new web3_js_1.PublicKey(data.slice(66, 98)).toString(),
+            startTime: Number(this.readInt64LE(data, 98)),
+            totalAmount: this.readUint64LE(data, 106).toString(),
+            cliffPeriod: Number(this.readInt64LE(data, schedule)),
+            vestingPeriod: Number(this.readInt64LE(data, schedule + 8)),
+            tgeBasisPoints: data.readUInt16LE(schedule + 16),
+            recipients,
+            recipientCount,
+            isFinalized: data[115] !== 0,
+            lastDistributionTime: Number(this.readInt64LE(data, 116)),
+            schedules
+        };
+    }
+    parseVersionedSchedule(data, offset) {
+        const unlockPoints = [];
+        for (let i = 0; i < data[offset + 26]; i++) {
+            const point = offset + 27 + i * VESTING_UNLOCK_POINT_LEN;
+            unlockPoints.push({
+                offset: Number(this.readInt64LE(data, point)),
+                cumulativeBasisPoints: data.readUInt16LE(point + 8)
+            });
+        }
+        return {
+            cliffPeriod: Number(this.readInt64LE(data, offset)),
+            vestingPeriod: Number(this.readInt64LE(data, offset + 8)),
+            tgeBasisPoints: data.readUInt16LE(offset + 16),
+            stepPeriod: Number(this.readInt64LE(data, offset + 18)),
+            unlockPoints
+        };
+    }
     readUint64LE(buffer, offset) {
         const low = buffer.readUInt32LE(offset);
         const high = buffer.readUInt32LE(offset + 4);
@@ -280,11 +357,12 @@ class SolanaService {
                     { pubkey: spl_token_1.TOKEN_PROGRAM_ID, isSigner: false, isWritable: false }, // 3. Token Program
                     { pubkey: web3_js_1.SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false }, // 4. Clock Sysvar
                     { pubkey: vaultAuthority, isSigner: false, isWritable: false }, // 5. Vault Authority PDA
+                    { pubkey: mintPubkey, isSigner: false, isWritable: false }, // 6. Mint
                     ...recipientATAs.map((ata) => ({
                         pubkey: ata,
                         isSigner: false,
                         isWritable: true
-                    })) // 6+. Recipient ATAs
+                    })) // 7+. Recipient ATAs
                 ],
                 data: Buffer.from([2]) // Instruction 2 = Claim
             });
@@ -16,6 +16,16 @@ import { ethers } from 'ethers';
 import fs from 'fs';
 import path from 'path';
 
+// Versioned Solana vesting layout (first byte = 2): fixed header, then
+// 59-byte recipient entries. Accounts still in the original 641-byte layout
+// use the sequential parser.
+const SOLANA_LAYOUT_VERSION = 2;
+const SOLANA_HEADER_LEN = 1198;
+const SOLANA_RECIPIENT_LEN = 59;
+const SOLANA_SCHEDULES_OFFSET = 271;
+const SOLANA_SCHEDULE_LEN = 187;
+const SOLANA_UNLOCK_POINT_LEN = 10;
+
 interface VestingContract {
   id: string;
   chain: 'solana' | 'bnb';
@@ -499,6 +509,7 @@ class AutoDistributionService extends EventEmitter {
         vestingPDA,                // vesting PDA
         vaultPDA,                  // vault PDA
         vaultAuthority,            // vault authority PDA
+        mintAddress,               // mint
         recipientATAs              // recipient ATAs
       );
       
@@ -711,6 +722,7 @@ class AutoDistributionService extends EventEmitter {
     vestingPDA: PublicKey,
     vaultPDA: PublicKey,
     vaultAuthority: PublicKey,
+    mint: PublicKey,
     recipientATAs: PublicKey[]
   ): TransactionInstruction {
     // Создаем данные инструкции: только 1 байт (инструкция)
@@ -727,9 +739,10 @@ class AutoDistributionService extends EventEmitter {
       { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // 3. Token Program
       { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false }, // 4. Clock Sysvar
       { pubkey: vaultAuthority, isSigner: false, isWritable: false },    // 5. Vault Authority PDA
+      { pubkey: mint, isSigner: false, isWritable: false },              // 6. Mint
     ];
     
-    // Добавляем ATA получателей (6+) точно как в 3-claim.js
+    // Добавляем ATA получателей (7+)
     for (const ata of recipientATAs) {
       keys.push({ pubkey: ata, isSigner: false, isWritable: true });
     }
@@ -746,6 +759,10 @@ class AutoDistributionService extends EventEmitter {
     if (!data || data.length < 640) {  // Updated minimum size with basis points
       throw new Error(`Invalid vesting account data. Length: ${data?.length || 0}, expected at least 640`);
     }
+
+    if (data[0] === SOLANA_LAYOUT_VERSION) {
+      return this.parseVersionedVestingAccount(data);
+    }
     
     let offset = 0;
     
@@ -836,6 +853,79 @@ class AutoDistributionService extends EventEmitter {
     };
   }
 
+  private parseVersionedVestingAccount(data: Buffer) {
+    if (data.length < SOLANA_HEADER_LEN) {
+      throw new Error(`Invalid vesting account data. Length: ${data.length}, expected at least ${SOLANA_HEADER_LEN}`);
+    }
+
+    // Schedule 0 also fills the single-schedule fields; recipients point at
+    // their own schedule through scheduleIndex
+    const schedule = SOLANA_SCHEDULES_OFFSET;
+    const schedules = [];
+    for (let i = 0; i < data[270]; i++) {
+      schedules.push(this.parseVersionedSchedule(data, SOLANA_SCHEDULES_OFFSET + i * SOLANA_SCHEDULE_LEN));
+    }
+    const recipientCount = data[114];
+
+    const recipients = [];
+    for (let i = 0; i < recipientCount; i++) {
+      const offset = SOLANA_HEADER_LEN + i * SOLANA_RECIPIENT_LEN;
+      if (offset + SOLANA_RECIPIENT_LEN > data.length) {
+        console.warn(`Not enough data for recipient ${i}, stopping parsing`);
+        break;
+      }
+
+      const basisPoints = data.readUInt16LE(offset + 32);
+      if (basisPoints > 0) {
+        recipients.push({
+          wallet: new PublicKey(data.slice(offset, offset + 32)).toString(),
+          basisPoints,
+          percentage: basisPoints / 100,
+          claimedAmount: Number(this.readUint64LE(data, offset + 34)),
+          lastClaimTime: Number(this.readInt64LE(data, offset + 42)),
+          revokedAt: Number(this.readInt64LE(data, offset + 50)),
+          scheduleIndex: data[offset + 58]
+        });
+      }
+    }
+
+    return {
+      isInitialized: data[1] !== 0,
+      initializer: new PublicKey(data.slice(2, 34)).toString(),
+      mint: new PublicKey(data.slice(34, 66)).toString(),
+      vault: new PublicKey(data.slice(66, 98)).toString(),
+      startTime: Number(this.readInt64LE(data, 98)),
+      totalAmount: Number(this.readUint64LE(data, 106)),
+      cliffPeriod: Number(this.readInt64LE(data, schedule)),
+      vestingPeriod: Number(this.readInt64LE(data, schedule + 8)),
+      tgeBasisPoints: data.readUInt16LE(schedule + 16),
+      recipientCount,
+      isFinalized: data[115] !== 0,
+      lastDistributionTime: Number(this.readInt64LE(data, 116)),
+      schedules,
+      recipients
+    };
+  }
+
+  private parseVersionedSchedule(data: Buffer, offset: number) {
+    const unlockPoints = [];
+    for (let i = 0; i < data[offset + 26]; i++) {
+      const point = offset + 27 + i * SOLANA_UNLOCK_POINT_LEN;
+      unlockPoints.push({
+        offset: Number(this.readInt64LE(data, point)),
+        cumulativeBasisPoints: data.readUInt16LE(point + 8)
+      });
+    }
+
+    return {
+      cliffPeriod: Number(this.readInt64LE(data, offset)),
+      vestingPeriod: Number(this.readInt64LE(data, offset + 8)),
+      tgeBasisPoints: data.readUInt16LE(offset + 16),
+      stepPeriod: Number(this.readInt64LE(data, offset + 18)),
+      unlockPoints
+    };
+  }
+
   // ✅ ADDED: Helper methods for reading BigInt from buffer
   private readUint64LE(buffer: Buffer, offset: number): bigint {
     const low = buffer.readUInt32LE(offset);
@@ -20,11 +20,36 @@ import { isAddressEqual } from '../utils/validation';
 import { logger } from '../utils/logger';
 import { ErrorHandler } from '../utils/errorHandler';
 
+// Versioned layout written by the program since the Migrate instruction
+// (first byte = 2): fixed header, then 59-byte recipient entries. Accounts
+// still in the original 641-byte layout use the sequential parser.
+const VESTING_LAYOUT_VERSION = 2;
+const VESTING_HEADER_LEN = 1198;
+const VESTING_RECIPIENT_LEN = 59;
+const VESTING_SCHEDULES_OFFSET = 271;
+const VESTING_SCHEDULE_LEN = 187;
+const VESTING_UNLOCK_POINT_LEN = 10;
+
 interface SolanaRecipient {
   wallet: string;
   basisPoints: number;  // ✅ UPDATED: Use basis points instead of percentage
   claimedAmount: string;
   lastClaimTime: number;
+  revokedAt?: number;  // 0 = not revoked; versioned layout only
+  scheduleIndex?: number;  // Index into SolanaVestingData.schedules
+}
+
+interface SolanaUnlockPoint {
+  offset: number;
+  cumulativeBasisPoints: number;
+}
+
+interface SolanaSchedule {
+  cliffPeriod: number;
+  vestingPeriod: number;
+  tgeBasisPoints: number;
+  stepPeriod: number;
+  unlockPoints: SolanaUnlockPoint[];
 }
 
 interface SolanaVestingData {
@@ -41,6 +66,7 @@ interface SolanaVestingData {
   recipientCount: number;
   isFinalized: boolean;  // ✅ UPDATED: New field from contract
   lastDistributionTime: number;  // ✅ UPDATED: New field from contract
+  schedules?: SolanaSchedule[];  // Every schedule of a versioned account
 }
 
 export class SolanaService {
@@ -117,6 +143,10 @@ export class SolanaService {
 
   private parseVestingAccount(data: Buffer): SolanaVestingData | null {
     try {
+      if (data[0] === VESTING_LAYOUT_VERSION) {
+        return this.parseVersionedVestingAccount(data);
+      }
+
       // ✅ UPDATED: Expected size with basis points (u16 instead of u8) and new fields
       if (data.length < 640) {  // Updated minimum size
         throw new Error(`Invalid account data length: ${data.length}, expected at least 640`);
@@ -215,6 +245,78 @@ export class SolanaService {
     }
   }
 
+  private parseVersionedVestingAccount(data: Buffer): SolanaVestingData {
+    if (data.length < VESTING_HEADER_LEN) {
+      throw new Error(`Invalid account data length: ${data.length}, expected at least ${VESTING_HEADER_LEN}`);
+    }
+
+    // Schedule 0 also fills the single-schedule fields; recipients point at
+    // their own schedule through scheduleIndex
+    const schedule = VESTING_SCHEDULES_OFFSET;
+    const schedules: SolanaSchedule[] = [];
+    for (let i = 0; i < data[270]; i++) {
+      schedules.push(this.parseVersionedSchedule(data, VESTING_SCHEDULES_OFFSET + i * VESTING_SCHEDULE_LEN));
+    }
+    const recipientCount = data[114];
+
+    const recipients: SolanaRecipient[] = [];
+    for (let i = 0; i < recipientCount; i++) {
+      const offset = VESTING_HEADER_LEN + i * VESTING_RECIPIENT_LEN;
+      if (offset + VESTING_RECIPIENT_LEN > data.length) {
+        logger.warn(`Not enough data for recipient ${i}, stopping parsing`);
+        break;
+      }
+
+      const basisPoints = data.readUInt16LE(offset + 32);
+      if (basisPoints > 0) {
+        recipients.push({
+          wallet: new PublicKey(data.slice(offset, offset + 32)).toString(),
+          basisPoints,
+          claimedAmount: this.readUint64LE(data, offset + 34).toString(),
+          lastClaimTime: Number(this.readInt64LE(data, offset + 42)),
+          revokedAt: Number(this.readInt64LE(data, offset + 50)),
+          scheduleIndex: data[offset + 58]
+        });
+      }
+    }
+
+    return {
+      isInitialized: data[1] !== 0,
+      initializer: new PublicKey(data.slice(2, 34)).toString(),
+      mint: new PublicKey(data.slice(34, 66)).toString(),
+      vault: new PublicKey(data.slice(66, 98)).toString(),
+      startTime: Number(this.readInt64LE(data, 98)),
+      totalAmount: this.readUint64LE(data, 106).toString(),
+      cliffPeriod: Number(this.readInt64LE(data, schedule)),
+      vestingPeriod: Number(this.readInt64LE(data, schedule + 8)),
+      tgeBasisPoints: data.readUInt16LE(schedule + 16),
+      recipients,
+      recipientCount,
+      isFinalized: data[115] !== 0,
+      lastDistributionTime: Number(this.readInt64LE(data, 116)),
+      schedules
+    };
+  }
+
+  private parseVersionedSchedule(data: Buffer, offset: number): SolanaSchedule {
+    const unlockPoints: SolanaUnlockPoint[] = [];
+    for (let i = 0; i < data[offset + 26]; i++) {
+      const point = offset + 27 + i * VESTING_UNLOCK_POINT_LEN;
+      unlockPoints.push({
+        offset: Number(this.readInt64LE(data, point)),
+        cumulativeBasisPoints: data.readUInt16LE(point + 8)
+      });
+    }
+
+    return {
+      cliffPeriod: Number(this.readInt64LE(data, offset)),
+      vestingPeriod: Number(this.readInt64LE(data, offset + 8)),
+      tgeBasisPoints: data.readUInt16LE(offset + 16),
+      stepPeriod: Number(this.readInt64LE(data, offset + 18)),
+      unlockPoints
+    };
+  }
+
   private readUint64LE(buffer: Buffer, offset: number): bigint {
     const low = buffer.readUInt32LE(offset);
     const high = buffer.readUInt32LE(offset + 4);
@@ -409,11 +511,12 @@ export class SolanaService {
           { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },      // 3. Token Program
           { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },   // 4. Clock Sysvar
           { pubkey: vaultAuthority, isSigner: false, isWritable: false },        // 5. Vault Authority PDA
+          { pubkey: mintPubkey, isSigner: false, isWritable: false },            // 6. Mint
           ...recipientATAs.map((ata: PublicKey) => ({ 
             pubkey: ata, 
             isSigner: false, 
             isWritable: true 
-          }))  // 6+. Recipient ATAs
+          }))  // 7+. Recipient ATAs
         ],
         data: Buffer.from([2]) // Instruction 2 = Claim
       });
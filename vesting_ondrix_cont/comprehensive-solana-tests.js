@@ -167,6 +167,7 @@ function createFundInstruction(
     sourceToken,
     vaultPDA,
     vestingPDA,
+    mint,
     amount
 ) {
     const data = Buffer.alloc(9);
@@ -182,6 +183,7 @@ function createFundInstruction(
             { pubkey: vestingPDA, isSigner: false, isWritable: true },
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
             { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+            { pubkey: mint, isSigner: false, isWritable: false },
         ],
         data,
     });
@@ -194,6 +196,7 @@ function createClaimInstruction(
     vestingPDA,
     vaultPDA,
     vaultAuthority,
+    mint,
     recipientATAs
 ) {
     const data = Buffer.alloc(1);
@@ -206,6 +209,7 @@ function createClaimInstruction(
         { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
         { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
         { pubkey: vaultAuthority, isSigner: false, isWritable: false },
+        { pubkey: mint, isSigner: false, isWritable: false },
     ];
     
     // Append recipient ATAs
@@ -708,6 +712,7 @@ async function testFundingSecurity(testData) {
                 funderTokenAccount,
                 vaultPDA,
                 vestingPDA,
+                mint,
                 CONFIG.TEST_TOKEN_AMOUNT
             );
             
@@ -728,6 +733,7 @@ async function testFundingSecurity(testData) {
                 funderTokenAccount,
                 fakeVaultPDA, // Wrong vault
                 vestingPDA,
+                mint,
                 CONFIG.TEST_TOKEN_AMOUNT
             );
             
@@ -746,6 +752,7 @@ async function testFundingSecurity(testData) {
                 funderTokenAccount,
                 vaultPDA,
                 vestingPDA,
+                mint,
                 0 // Zero amount
             );
             
@@ -764,6 +771,7 @@ async function testFundingSecurity(testData) {
                 funderTokenAccount,
                 vaultPDA,
                 vestingPDA,
+                mint,
                 CONFIG.TEST_TOKEN_AMOUNT
             );
             
@@ -783,6 +791,7 @@ async function testFundingSecurity(testData) {
                     funderTokenAccount,
                     vaultPDA,
                     vestingPDA,
+                    mint,
                     100 * 1e9
                 );
                 
@@ -852,6 +861,7 @@ async function testDistributionSecurity(testData) {
                 vestingPDA,
                 vaultPDA,
                 vaultAuthority,
+                mint,
                 recipientATAs
             );
             
@@ -872,6 +882,7 @@ async function testDistributionSecurity(testData) {
                 vestingPDA,
                 vaultPDA,
                 vaultAuthority,
+                mint,
                 recipientATAs
             );
             
@@ -892,6 +903,7 @@ async function testDistributionSecurity(testData) {
                 vestingPDA,
                 vaultPDA,
                 fakeAuthority, // Wrong authority
+                mint,
                 recipientATAs
             );
             
@@ -918,6 +930,7 @@ async function testDistributionSecurity(testData) {
                 vestingPDA,
                 vaultPDA,
                 vaultAuthority,
+                mint,
                 wrongATAs // Wrong ATAs
             );
             
@@ -938,6 +951,7 @@ async function testDistributionSecurity(testData) {
                 fakeVestingPDA, // Wrong vesting PDA
                 vaultPDA,
                 vaultAuthority,
+                mint,
                 recipientATAs
             );
             
@@ -960,6 +974,7 @@ async function testDistributionSecurity(testData) {
                 vestingPDA,
                 vaultPDA,
                 vaultAuthority,
+                mint,
                 recipientATAs
             );
             
@@ -979,6 +994,7 @@ async function testDistributionSecurity(testData) {
                     vestingPDA,
                     vaultPDA,
                     vaultAuthority,
+                    mint,
                     recipientATAs
                 );
                 
@@ -1277,6 +1293,7 @@ async function testEconomicAttacks(testData) {
                 attackerTokenAccount,
                 vaultPDA,
                 vestingPDA,
+                mint,
                 Number(maxAmount)
             );
             
@@ -1308,6 +1325,7 @@ async function testEconomicAttacks(testData) {
                 vestingPDA,
                 vaultPDA,
                 vaultAuthority,
+                mint,
                 fakeATAs
             );
             
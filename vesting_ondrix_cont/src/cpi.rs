@@ -0,0 +1,204 @@
+//! Cross-program invocation helpers for programs that create, fund or
+//! claim vestings. Every helper takes the signer seeds of the calling
+//! program, so the initializer, funder or caller may be one of its PDAs;
+//! pass `&[]` when the signer is a regular wallet.
+//!
+//! The instructions come from the builders in [`crate::instruction`]; the
+//! helpers only supply the matching account infos.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+use crate::instruction::{self, InitializeOptions, RecipientData};
+
+pub struct InitializeVesting<'a> {
+    pub initializer: AccountInfo<'a>,
+    pub vesting: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+    pub system_program: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub rent: AccountInfo<'a>,
+}
+
+pub struct Fund<'a> {
+    pub funder: AccountInfo<'a>,
+    pub source: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub vesting: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub clock: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+}
+
+pub struct Claim<'a> {
+    pub caller: AccountInfo<'a>,
+    pub vesting: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub clock: AccountInfo<'a>,
+    pub vault_authority: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+    /// Fee pool, ATA creation and recipient accounts that
+    /// [`instruction::claim`] adds for the given options, in any order.
+    pub remaining_accounts: Vec<AccountInfo<'a>>,
+}
+
+pub struct ClaimOwn<'a> {
+    pub recipient: AccountInfo<'a>,
+    pub vesting: AccountInfo<'a>,
+    pub vault: AccountInfo<'a>,
+    pub recipient_token_account: AccountInfo<'a>,
+    pub token_program: AccountInfo<'a>,
+    pub clock: AccountInfo<'a>,
+    pub vault_authority: AccountInfo<'a>,
+    pub mint: AccountInfo<'a>,
+    /// System and associated token programs when `create_missing_ata` is set.
+    pub remaining_accounts: Vec<AccountInfo<'a>>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_vesting<'a>(
+    program: AccountInfo<'a>,
+    accounts: InitializeVesting<'a>,
+    signer_seeds: &[&[&[u8]]],
+    recipients: Vec<RecipientData>,
+    cliff_period: i64,
+    vesting_period: i64,
+    tge_basis_points: u16,
+    nonce: u64,
+    options: InitializeOptions,
+) -> ProgramResult {
+    let instruction = instruction::initialize_vesting(
+        program.key,
+        accounts.initializer.key,
+        accounts.mint.key,
+        accounts.token_program.key,
+        recipients,
+        cliff_period,
+        vesting_period,
+        tge_basis_points,
+        nonce,
+        options,
+    )?;
+
+    invoke_signed(
+        &instruction,
+        &[
+            accounts.initializer,
+            accounts.vesting,
+            accounts.vault,
+            accounts.mint,
+            accounts.system_program,
+            accounts.token_program,
+            accounts.rent,
+            program,
+        ],
+        signer_seeds,
+    )
+}
+
+pub fn fund<'a>(
+    program: AccountInfo<'a>,
+    accounts: Fund<'a>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> ProgramResult {
+    let instruction = instruction::fund(
+        program.key,
+        accounts.funder.key,
+        accounts.source.key,
+        accounts.vesting.key,
+        accounts.mint.key,
+        accounts.token_program.key,
+        amount,
+    );
+
+    invoke_signed(
+        &instruction,
+        &[
+            accounts.funder,
+            accounts.source,
+            accounts.vault,
+            accounts.vesting,
+            accounts.token_program,
+            accounts.clock,
+            accounts.mint,
+            program,
+        ],
+        signer_seeds,
+    )
+}
+
+/// Invokes `Claim`; options as in [`instruction::claim`]. The `ata_payer` must
+/// sign, either as a wallet signer of the outer transaction or as a PDA of the
+/// calling program through `signer_seeds`.
+pub fn claim<'a>(
+    program: AccountInfo<'a>,
+    accounts: Claim<'a>,
+    signer_seeds: &[&[&[u8]]],
+    recipient_wallets: &[Pubkey],
+    keeper_reward: bool,
+    ata_payer: Option<&Pubkey>,
+) -> ProgramResult {
+    let instruction = instruction::claim(
+        program.key,
+        accounts.caller.key,
+        accounts.vesting.key,
+        accounts.mint.key,
+        accounts.token_program.key,
+        recipient_wallets,
+        keeper_reward,
+        ata_payer,
+    );
+
+    let mut account_infos = vec![
+        accounts.caller,
+        accounts.vesting,
+        accounts.vault,
+        accounts.token_program,
+        accounts.clock,
+        accounts.vault_authority,
+        accounts.mint,
+    ];
+    account_infos.extend(accounts.remaining_accounts);
+    account_infos.push(program);
+
+    invoke_signed(&instruction, &account_infos, signer_seeds)
+}
+
+/// Invokes `ClaimOwn` for a recipient that is one of the calling program's PDAs.
+pub fn claim_own<'a>(
+    program: AccountInfo<'a>,
+    accounts: ClaimOwn<'a>,
+    signer_seeds: &[&[&[u8]]],
+    create_missing_ata: bool,
+) -> ProgramResult {
+    let instruction = instruction::claim_own(
+        program.key,
+        accounts.recipient.key,
+        accounts.vesting.key,
+        accounts.mint.key,
+        accounts.token_program.key,
+        create_missing_ata,
+    );
+
+    let mut account_infos = vec![
+        accounts.recipient,
+        accounts.vesting,
+        accounts.vault,
+        accounts.recipient_token_account,
+        accounts.token_program,
+        accounts.clock,
+        accounts.vault_authority,
+        accounts.mint,
+    ];
+    account_infos.extend(accounts.remaining_accounts);
+    account_infos.push(program);
+
+    invoke_signed(&instruction, &account_infos, signer_seeds)
+}
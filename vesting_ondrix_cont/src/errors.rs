@@ -119,6 +119,75 @@ pub enum VestingError {
     
     #[error("Invalid recipient wallet")]
     InvalidRecipientWallet,
+
+    #[error("Recipient not found")]
+    RecipientNotFound,
+
+    #[error("Invalid keeper reward")]
+    InvalidKeeperReward,
+
+    #[error("Invalid recipient index")]
+    InvalidRecipientIndex,
+
+    #[error("Invalid associated token program")]
+    InvalidAssociatedTokenProgram,
+
+    #[error("Vesting is not revocable")]
+    NotRevocable,
+
+    #[error("Recipient already revoked")]
+    AlreadyRevoked,
+
+    #[error("Invalid clawback account")]
+    InvalidClawbackAccount,
+
+    #[error("Vesting not fully distributed")]
+    NotFullyDistributed,
+
+    #[error("Invalid rent recipient")]
+    InvalidRentRecipient,
+
+    #[error("Unauthorized funder")]
+    UnauthorizedFunder,
+
+    #[error("Invalid start time")]
+    InvalidStartTime,
+
+    #[error("Invalid unlock schedule")]
+    InvalidUnlockSchedule,
+
+    #[error("Invalid step period")]
+    InvalidStepPeriod,
+
+    #[error("Invalid schedule index")]
+    InvalidScheduleIndex,
+
+    #[error("Instruction not supported in this vesting mode")]
+    UnsupportedVestingMode,
+
+    #[error("Invalid merkle root")]
+    InvalidMerkleRoot,
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof,
+
+    #[error("Account layout is outdated, run Migrate first")]
+    MigrationRequired,
+
+    #[error("Account already uses the current layout")]
+    AlreadyMigrated,
+
+    #[error("Claims and distribution are paused")]
+    VestingPaused,
+
+    #[error("Vesting is not paused")]
+    NotPaused,
+
+    #[error("Pause budget exhausted")]
+    PauseBudgetExhausted,
+
+    #[error("Mint extension is not supported")]
+    UnsupportedMintExtension,
 }
 
 impl From<VestingError> for ProgramError {
@@ -0,0 +1,211 @@
+use arrayref::{array_ref, array_refs};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+/// Layout version written as the first byte of every event.
+pub const EVENT_VERSION: u8 = 1;
+
+const KIND_VESTING_INITIALIZED: u8 = 0;
+const KIND_FUNDED: u8 = 1;
+const KIND_RECIPIENT_PAID: u8 = 2;
+
+const VESTING_INITIALIZED_LEN: usize = 105;
+const FUNDED_LEN: usize = 80;
+const RECIPIENT_PAID_LEN: usize = 96;
+
+/// Events logged through `sol_log_data` as `[version, kind, payload]`.
+/// Integers are little-endian, like the account layouts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VestingEvent {
+    VestingInitialized {
+        vesting: Pubkey,
+        initializer: Pubkey,
+        mint: Pubkey,
+        recipient_count: u8,
+        /// 0 when the vesting starts on funding
+        start_time: i64,
+    },
+    Funded {
+        vesting: Pubkey,
+        funder: Pubkey,
+        amount: u64,
+        start_time: i64,
+    },
+    RecipientPaid {
+        vesting: Pubkey,
+        wallet: Pubkey,
+        /// Gross amount debited from the vault
+        amount: u64,
+        /// Amount the recipient's token account received; lower than `amount`
+        /// when a Token-2022 transfer fee is withheld
+        net_amount: u64,
+        /// Total paid to the recipient so far, including this payment
+        claimed_amount: u64,
+        timestamp: i64,
+    },
+}
+
+impl VestingEvent {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![EVENT_VERSION];
+        match self {
+            VestingEvent::VestingInitialized {
+                vesting,
+                initializer,
+                mint,
+                recipient_count,
+                start_time,
+            } => {
+                buf.push(KIND_VESTING_INITIALIZED);
+                buf.extend_from_slice(vesting.as_ref());
+                buf.extend_from_slice(initializer.as_ref());
+                buf.extend_from_slice(mint.as_ref());
+                buf.push(*recipient_count);
+                buf.extend_from_slice(&start_time.to_le_bytes());
+            }
+            VestingEvent::Funded { vesting, funder, amount, start_time } => {
+                buf.push(KIND_FUNDED);
+                buf.extend_from_slice(vesting.as_ref());
+                buf.extend_from_slice(funder.as_ref());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&start_time.to_le_bytes());
+            }
+            VestingEvent::RecipientPaid {
+                vesting,
+                wallet,
+                amount,
+                net_amount,
+                claimed_amount,
+                timestamp,
+            } => {
+                buf.push(KIND_RECIPIENT_PAID);
+                buf.extend_from_slice(vesting.as_ref());
+                buf.extend_from_slice(wallet.as_ref());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&net_amount.to_le_bytes());
+                buf.extend_from_slice(&claimed_amount.to_le_bytes());
+                buf.extend_from_slice(&timestamp.to_le_bytes());
+            }
+        }
+        buf
+    }
+
+    /// Decodes one `Program data:` entry. Returns `None` for data that is
+    /// not a vesting event of a known version, so other logs can be skipped.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let (&version, rest) = data.split_first()?;
+        if version != EVENT_VERSION {
+            return None;
+        }
+        let (&kind, payload) = rest.split_first()?;
+
+        match kind {
+            KIND_VESTING_INITIALIZED if payload.len() == VESTING_INITIALIZED_LEN => {
+                let payload = array_ref![payload, 0, VESTING_INITIALIZED_LEN];
+                let (vesting, initializer, mint, recipient_count, start_time) =
+                    array_refs![payload, 32, 32, 32, 1, 8];
+                Some(VestingEvent::VestingInitialized {
+                    vesting: Pubkey::new_from_array(*vesting),
+                    initializer: Pubkey::new_from_array(*initializer),
+                    mint: Pubkey::new_from_array(*mint),
+                    recipient_count: recipient_count[0],
+                    start_time: i64::from_le_bytes(*start_time),
+                })
+            }
+            KIND_FUNDED if payload.len() == FUNDED_LEN => {
+                let payload = array_ref![payload, 0, FUNDED_LEN];
+                let (vesting, funder, amount, start_time) = array_refs![payload, 32, 32, 8, 8];
+                Some(VestingEvent::Funded {
+                    vesting: Pubkey::new_from_array(*vesting),
+                    funder: Pubkey::new_from_array(*funder),
+                    amount: u64::from_le_bytes(*amount),
+                    start_time: i64::from_le_bytes(*start_time),
+                })
+            }
+            KIND_RECIPIENT_PAID if payload.len() == RECIPIENT_PAID_LEN => {
+                let payload = array_ref![payload, 0, RECIPIENT_PAID_LEN];
+                let (vesting, wallet, amount, net_amount, claimed_amount, timestamp) =
+                    array_refs![payload, 32, 32, 8, 8, 8, 8];
+                Some(VestingEvent::RecipientPaid {
+                    vesting: Pubkey::new_from_array(*vesting),
+                    wallet: Pubkey::new_from_array(*wallet),
+                    amount: u64::from_le_bytes(*amount),
+                    net_amount: u64::from_le_bytes(*net_amount),
+                    claimed_amount: u64::from_le_bytes(*claimed_amount),
+                    timestamp: i64::from_le_bytes(*timestamp),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn emit(&self) {
+        sol_log_data(&[&self.encode()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(event: VestingEvent) {
+        let encoded = event.encode();
+        let decoded = VestingEvent::decode(&encoded).unwrap();
+        assert_eq!(decoded, event);
+        assert_eq!(decoded.encode(), encoded);
+    }
+
+    fn recipient_paid() -> VestingEvent {
+        VestingEvent::RecipientPaid {
+            vesting: Pubkey::new_unique(),
+            wallet: Pubkey::new_unique(),
+            amount: 1000,
+            net_amount: 990,
+            claimed_amount: 2500,
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        round_trip(VestingEvent::VestingInitialized {
+            vesting: Pubkey::new_unique(),
+            initializer: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            recipient_count: 10,
+            start_time: 0,
+        });
+        round_trip(VestingEvent::Funded {
+            vesting: Pubkey::new_unique(),
+            funder: Pubkey::new_unique(),
+            amount: u64::MAX,
+            start_time: -1,
+        });
+        round_trip(recipient_paid());
+    }
+
+    #[test]
+    fn test_decode_rejects_unknown_data() {
+        let encoded = recipient_paid().encode();
+        assert_eq!(encoded.len(), 2 + RECIPIENT_PAID_LEN);
+
+        let mut unknown_version = encoded.clone();
+        unknown_version[0] = EVENT_VERSION + 1;
+        assert_eq!(VestingEvent::decode(&unknown_version), None);
+
+        let mut unknown_kind = encoded.clone();
+        unknown_kind[1] = KIND_RECIPIENT_PAID + 1;
+        assert_eq!(VestingEvent::decode(&unknown_kind), None);
+
+        // A known kind with the payload of another one
+        let mut wrong_kind = encoded.clone();
+        wrong_kind[1] = KIND_FUNDED;
+        assert_eq!(VestingEvent::decode(&wrong_kind), None);
+
+        assert_eq!(VestingEvent::decode(&encoded[..encoded.len() - 1]), None);
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(VestingEvent::decode(&trailing), None);
+        assert_eq!(VestingEvent::decode(&[EVENT_VERSION]), None);
+        assert_eq!(VestingEvent::decode(&[]), None);
+    }
+}
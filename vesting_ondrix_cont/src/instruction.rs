@@ -1,14 +1,20 @@
 use solana_program::{
+    instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
+    system_program,
+    sysvar,
 };
-use crate::state::{MAX_RECIPIENTS, BASIS_POINTS_TOTAL};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use crate::state::{UnlockPoint, VestingSchedule, MAX_RECIPIENTS, MAX_SCHEDULES, MAX_UNLOCK_POINTS, BASIS_POINTS_TOTAL};
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum InstructionError {
     InvalidInstructionData,
     InvalidRecipientCount,
     InvalidTotalPercentage,
+    InconsistentOptions,
 }
 
 impl From<InstructionError> for ProgramError {
@@ -17,7 +23,8 @@ impl From<InstructionError> for ProgramError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum VestingInstruction {
     InitializeVesting {
         recipients: Vec<RecipientData>,
@@ -25,18 +32,550 @@ pub enum VestingInstruction {
         vesting_period: i64,
         tge_basis_points: u16,
         nonce: u64,  
+        options: InitializeOptions,
     },
     Fund(u64),
-    Claim,
-    
+    /// A trailing `1` byte asks the program to create missing recipient ATAs
+    Claim {
+        create_missing_atas: bool,
+    },
+    ClaimOwn {
+        create_missing_ata: bool,
+    },
+    DistributeSubset {
+        recipient_indices: Vec<u8>,
+        create_missing_atas: bool,
+    },
+    Revoke {
+        recipient_index: u8,
+    },
+    RotateRecipientWallet {
+        new_wallet: Pubkey,
+    },
+    Close,
+    SweepSurplus,
+    /// Appends recipients before funding; an optional trailing block holds one
+    /// schedule index per added recipient
+    AddRecipients {
+        recipients: Vec<RecipientData>,
+        schedule_indices: Vec<u8>,
+    },
+    /// Creates the `["recipient", vesting, wallet]` PDA of a vesting in
+    /// recipient-account mode; the schedule index byte is optional
+    CreateRecipientAccount {
+        wallet: Pubkey,
+        basis_points: u16,
+        schedule_index: u8,
+    },
+    /// ClaimOwn for recipient-account mode; only the recipient PDA is written
+    ClaimRecipientAccount {
+        create_missing_ata: bool,
+    },
+    /// Claim of a merkle leaf `(signer, allocation)` proven by sibling hashes
+    ClaimMerkle {
+        allocation: u64,
+        proof: Vec<[u8; 32]>,
+        create_missing_ata: bool,
+    },
+    /// Rewrites a v1 vesting account in the current versioned layout
+    Migrate,
+    /// Read-only: returns a `ClaimableQuery` through return data for `count`
+    /// recipients from `first_index` (0 = as many as fit), at `timestamp` or now
+    QueryClaimable {
+        first_index: u8,
+        count: u8,
+        timestamp: Option<i64>,
+    },
+    /// Guardian only: blocks claims and distribution until Unpause or until
+    /// the pause expires; accrual continues meanwhile
+    Pause,
+    Unpause,
+    /// Read-only: `QueryClaimable` for one merkle leaf `(wallet, allocation)`.
+    /// The proof is only checked while the wallet has no claim receipt yet
+    QueryMerkleClaimable {
+        wallet: Pubkey,
+        allocation: u64,
+        proof: Vec<[u8; 32]>,
+        timestamp: Option<i64>,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RecipientData {
     pub wallet: Pubkey,
     pub basis_points: u16,  
 }
 
+/// Optional settings appended after the recipient records of `InitializeVesting`.
+/// Each entry is a one byte tag followed by its fixed-size payload; omitted
+/// entries keep their defaults, so the legacy layout stays valid.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InitializeOptions {
+    /// Any signer may call the distribution, not only the initializer
+    pub permissionless_distribution: bool,
+    /// Lamports paid to the caller from the fee pool PDA for a distribution
+    /// of at least 0.1% of the total, at most once per hour
+    pub keeper_reward_lamports: u64,
+    /// Opt into revocation; fixed for the lifetime of the vesting
+    pub revocable: bool,
+    pub revoker: Pubkey,
+    /// Token account receiving unvested tokens of revoked recipients
+    pub clawback_account: Pubkey,
+    /// Receives the lamports on `Close`; the initializer when unset
+    pub rent_recipient: Option<Pubkey>,
+    /// Only signer allowed to `Fund`; the initializer when unset
+    pub funder: Option<Pubkey>,
+    /// Exact amount `Fund` must transfer; 0 accepts any amount
+    pub expected_amount: u64,
+    /// Fixed TGE timestamp; when unset the vesting starts at funding time
+    pub start_time: Option<i64>,
+    /// Piecewise schedule replacing TGE/cliff/linear; the header periods must be 0
+    pub unlock_points: Vec<UnlockPoint>,
+    /// Release the linear part in whole steps of this many seconds
+    pub step_period: i64,
+    /// Schedules 1.. of the account; schedule 0 comes from the instruction header
+    pub additional_schedules: Vec<VestingSchedule>,
+    /// Schedule index of each recipient, in recipient order; all use schedule 0 when empty
+    pub recipient_schedule_indices: Vec<u8>,
+    /// Keep recipients in per-wallet PDAs instead of the vesting account;
+    /// the instruction then carries no inline recipients
+    pub recipient_accounts: bool,
+    /// Merkle root over (wallet, allocation) leaves replacing inline recipients
+    pub merkle_root: Option<[u8; 32]>,
+    /// Sum of all leaf allocations, checked against the funded amount
+    pub merkle_total_allocation: u64,
+    /// Key allowed to pause claims and distribution for a bounded time
+    pub guardian: Option<Pubkey>,
+}
+
+const OPTION_PERMISSIONLESS_DISTRIBUTION: u8 = 1;
+const OPTION_REVOCABLE: u8 = 2;
+const OPTION_RENT_RECIPIENT: u8 = 3;
+const OPTION_FUNDING: u8 = 4;
+const OPTION_START_TIME: u8 = 5;
+const OPTION_UNLOCK_POINTS: u8 = 6;
+const OPTION_STEP_PERIOD: u8 = 7;
+const OPTION_SCHEDULES: u8 = 8;
+const OPTION_RECIPIENT_SCHEDULES: u8 = 9;
+const OPTION_RECIPIENT_ACCOUNTS: u8 = 10;
+const OPTION_MERKLE_ROOT: u8 = 11;
+const OPTION_GUARDIAN: u8 = 12;
+
+pub const MAX_MERKLE_PROOF_LEN: usize = 32;
+
+/// Entries that fit into `MAX_RETURN_DATA` next to the query header.
+pub const MAX_QUERY_ENTRIES: usize = 42;
+
+/// Amounts of one recipient as returned by `QueryClaimable`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClaimableAmounts {
+    pub vested: u64,
+    pub claimed: u64,
+    pub claimable: u64,
+}
+
+/// Return data of `QueryClaimable`: `timestamp i64, recipient_count u8,
+/// entry_count u8` followed by `vested, claimed, claimable` as u64 per entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimableQuery {
+    pub timestamp: i64,
+    /// All recipients that can be queried, for paging with `first_index`
+    pub recipient_count: u8,
+    pub entries: Vec<ClaimableAmounts>,
+}
+
+impl ClaimableQuery {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(10 + self.entries.len() * 24);
+        buf.extend_from_slice(&self.timestamp.to_le_bytes());
+        buf.push(self.recipient_count);
+        buf.push(self.entries.len() as u8);
+        for entry in &self.entries {
+            buf.extend_from_slice(&entry.vested.to_le_bytes());
+            buf.extend_from_slice(&entry.claimed.to_le_bytes());
+            buf.extend_from_slice(&entry.claimable.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (timestamp, rest) = unpack_i64(data)?;
+        let (&recipient_count, rest) = rest.split_first()
+            .ok_or(InstructionError::InvalidInstructionData)?;
+        let (&entry_count, mut rest) = rest.split_first()
+            .ok_or(InstructionError::InvalidInstructionData)?;
+
+        let mut entries = Vec::with_capacity(entry_count as usize);
+        for _ in 0..entry_count {
+            let (vested, after_vested) = unpack_u64(rest)?;
+            let (claimed, after_claimed) = unpack_u64(after_vested)?;
+            let (claimable, after_claimable) = unpack_u64(after_claimed)?;
+            entries.push(ClaimableAmounts { vested, claimed, claimable });
+            rest = after_claimable;
+        }
+        if !rest.is_empty() {
+            return Err(InstructionError::InvalidInstructionData.into());
+        }
+
+        Ok(ClaimableQuery { timestamp, recipient_count, entries })
+    }
+}
+
+impl InitializeOptions {
+    fn unpack(mut data: &[u8]) -> Result<Self, ProgramError> {
+        let mut options = InitializeOptions::default();
+        let mut seen_tags: u32 = 0;
+
+        while let Some((&tag, rest)) = data.split_first() {
+            if tag >= 32 || seen_tags & (1 << tag) != 0 {
+                return Err(InstructionError::InvalidInstructionData.into());
+            }
+            seen_tags |= 1 << tag;
+
+            data = match tag {
+                OPTION_PERMISSIONLESS_DISTRIBUTION => {
+                    let (keeper_reward, rest) = unpack_u64(rest)?;
+                    options.permissionless_distribution = true;
+                    options.keeper_reward_lamports = keeper_reward;
+                    rest
+                }
+                OPTION_REVOCABLE => {
+                    let (revoker, rest) = unpack_pubkey(rest)?;
+                    let (clawback_account, rest) = unpack_pubkey(rest)?;
+                    options.revocable = true;
+                    options.revoker = revoker;
+                    options.clawback_account = clawback_account;
+                    rest
+                }
+                OPTION_RENT_RECIPIENT => {
+                    let (rent_recipient, rest) = unpack_pubkey(rest)?;
+                    options.rent_recipient = Some(rent_recipient);
+                    rest
+                }
+                OPTION_FUNDING => {
+                    let (funder, rest) = unpack_pubkey(rest)?;
+                    let (expected_amount, rest) = unpack_u64(rest)?;
+                    options.funder = Some(funder);
+                    options.expected_amount = expected_amount;
+                    rest
+                }
+                OPTION_START_TIME => {
+                    let (start_time, rest) = unpack_i64(rest)?;
+                    options.start_time = Some(start_time);
+                    rest
+                }
+                OPTION_UNLOCK_POINTS => {
+                    let (&point_count, rest) = rest.split_first()
+                        .ok_or(InstructionError::InvalidInstructionData)?;
+                    if point_count == 0 {
+                        return Err(InstructionError::InvalidInstructionData.into());
+                    }
+                    let (unlock_points, rest) = unpack_unlock_points(point_count, rest)?;
+                    options.unlock_points = unlock_points;
+                    rest
+                }
+                OPTION_STEP_PERIOD => {
+                    let (step_period, rest) = unpack_i64(rest)?;
+                    options.step_period = step_period;
+                    rest
+                }
+                OPTION_SCHEDULES => {
+                    let (&schedule_count, mut rest) = rest.split_first()
+                        .ok_or(InstructionError::InvalidInstructionData)?;
+                    if schedule_count == 0 || schedule_count as usize >= MAX_SCHEDULES {
+                        return Err(InstructionError::InvalidInstructionData.into());
+                    }
+                    for _ in 0..schedule_count {
+                        let (schedule, after_schedule) = unpack_schedule(rest)?;
+                        options.additional_schedules.push(schedule);
+                        rest = after_schedule;
+                    }
+                    rest
+                }
+                OPTION_RECIPIENT_SCHEDULES => {
+                    let (&count, rest) = rest.split_first()
+                        .ok_or(InstructionError::InvalidInstructionData)?;
+                    if count == 0 || count as usize > MAX_RECIPIENTS || rest.len() < count as usize {
+                        return Err(InstructionError::InvalidInstructionData.into());
+                    }
+                    let (indices, rest) = rest.split_at(count as usize);
+                    options.recipient_schedule_indices = indices.to_vec();
+                    rest
+                }
+                OPTION_RECIPIENT_ACCOUNTS => {
+                    options.recipient_accounts = true;
+                    rest
+                }
+                OPTION_MERKLE_ROOT => {
+                    let (merkle_root, rest) = unpack_hash(rest)?;
+                    let (total_allocation, rest) = unpack_u64(rest)?;
+                    options.merkle_root = Some(merkle_root);
+                    options.merkle_total_allocation = total_allocation;
+                    rest
+                }
+                OPTION_GUARDIAN => {
+                    let (guardian, rest) = unpack_pubkey(rest)?;
+                    options.guardian = Some(guardian);
+                    rest
+                }
+                _ => return Err(InstructionError::InvalidInstructionData.into()),
+            };
+        }
+
+        Ok(options)
+    }
+
+    /// Rejects settings the encoding would drop: a keeper reward without
+    /// permissionless distribution, an expected amount without a funder,
+    /// revocation keys without `revocable` and a merkle allocation without a root.
+    pub fn validate(&self) -> Result<(), InstructionError> {
+        if (self.keeper_reward_lamports != 0 && !self.permissionless_distribution)
+            || (self.expected_amount != 0 && self.funder.is_none())
+            || (!self.revocable
+                && (self.revoker != Pubkey::default() || self.clawback_account != Pubkey::default()))
+            || (self.merkle_total_allocation != 0 && self.merkle_root.is_none())
+        {
+            return Err(InstructionError::InconsistentOptions);
+        }
+        Ok(())
+    }
+
+    /// Appends the TLV entries of all non-default settings in tag order.
+    /// Panics on options that fail `validate`, which would otherwise be lost.
+    fn pack_into(&self, buf: &mut Vec<u8>) {
+        if self.validate().is_err() {
+            panic!("Inconsistent InitializeOptions");
+        }
+        if self.permissionless_distribution {
+            buf.push(OPTION_PERMISSIONLESS_DISTRIBUTION);
+            buf.extend_from_slice(&self.keeper_reward_lamports.to_le_bytes());
+        }
+        if self.revocable {
+            buf.push(OPTION_REVOCABLE);
+            buf.extend_from_slice(self.revoker.as_ref());
+            buf.extend_from_slice(self.clawback_account.as_ref());
+        }
+        if let Some(rent_recipient) = &self.rent_recipient {
+            buf.push(OPTION_RENT_RECIPIENT);
+            buf.extend_from_slice(rent_recipient.as_ref());
+        }
+        if let Some(funder) = &self.funder {
+            buf.push(OPTION_FUNDING);
+            buf.extend_from_slice(funder.as_ref());
+            buf.extend_from_slice(&self.expected_amount.to_le_bytes());
+        }
+        if let Some(start_time) = self.start_time {
+            buf.push(OPTION_START_TIME);
+            buf.extend_from_slice(&start_time.to_le_bytes());
+        }
+        if !self.unlock_points.is_empty() {
+            buf.push(OPTION_UNLOCK_POINTS);
+            buf.push(self.unlock_points.len() as u8);
+            pack_unlock_points(&self.unlock_points, buf);
+        }
+        if self.step_period != 0 {
+            buf.push(OPTION_STEP_PERIOD);
+            buf.extend_from_slice(&self.step_period.to_le_bytes());
+        }
+        if !self.additional_schedules.is_empty() {
+            buf.push(OPTION_SCHEDULES);
+            buf.push(self.additional_schedules.len() as u8);
+            for schedule in &self.additional_schedules {
+                pack_schedule(schedule, buf);
+            }
+        }
+        if !self.recipient_schedule_indices.is_empty() {
+            buf.push(OPTION_RECIPIENT_SCHEDULES);
+            buf.push(self.recipient_schedule_indices.len() as u8);
+            buf.extend_from_slice(&self.recipient_schedule_indices);
+        }
+        if self.recipient_accounts {
+            buf.push(OPTION_RECIPIENT_ACCOUNTS);
+        }
+        if let Some(merkle_root) = &self.merkle_root {
+            buf.push(OPTION_MERKLE_ROOT);
+            buf.extend_from_slice(merkle_root);
+            buf.extend_from_slice(&self.merkle_total_allocation.to_le_bytes());
+        }
+        if let Some(guardian) = &self.guardian {
+            buf.push(OPTION_GUARDIAN);
+            buf.extend_from_slice(guardian.as_ref());
+        }
+    }
+}
+
+fn pack_schedule(schedule: &VestingSchedule, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&schedule.cliff_period.to_le_bytes());
+    buf.extend_from_slice(&schedule.vesting_period.to_le_bytes());
+    buf.extend_from_slice(&schedule.tge_basis_points.to_le_bytes());
+    buf.extend_from_slice(&schedule.step_period.to_le_bytes());
+    buf.push(schedule.unlock_point_count);
+    pack_unlock_points(schedule.active_unlock_points(), buf);
+}
+
+fn pack_unlock_points(points: &[UnlockPoint], buf: &mut Vec<u8>) {
+    for point in points {
+        buf.extend_from_slice(&point.offset.to_le_bytes());
+        buf.extend_from_slice(&point.cumulative_basis_points.to_le_bytes());
+    }
+}
+
+fn pack_recipients(recipients: &[RecipientData], buf: &mut Vec<u8>) {
+    for recipient in recipients {
+        buf.extend_from_slice(recipient.wallet.as_ref());
+        buf.extend_from_slice(&recipient.basis_points.to_le_bytes());
+    }
+}
+
+/// Schedule encoding: cliff i64, vesting i64, TGE bps u16, step i64,
+/// point count u8, then that many (offset i64, cumulative bps u16) points.
+fn unpack_schedule(data: &[u8]) -> Result<(VestingSchedule, &[u8]), ProgramError> {
+    let (cliff_period, rest) = unpack_i64(data)?;
+    let (vesting_period, rest) = unpack_i64(rest)?;
+    let (tge_basis_points, rest) = unpack_u16(rest)?;
+    let (step_period, rest) = unpack_i64(rest)?;
+    let (&point_count, rest) = rest.split_first()
+        .ok_or(InstructionError::InvalidInstructionData)?;
+    let (unlock_points, rest) = unpack_unlock_points(point_count, rest)?;
+
+    let mut schedule = VestingSchedule {
+        cliff_period,
+        vesting_period,
+        tge_basis_points,
+        unlock_point_count: point_count,
+        step_period,
+        ..VestingSchedule::default()
+    };
+    schedule.unlock_points[..unlock_points.len()].copy_from_slice(&unlock_points);
+    Ok((schedule, rest))
+}
+
+fn unpack_unlock_points(count: u8, mut data: &[u8]) -> Result<(Vec<UnlockPoint>, &[u8]), ProgramError> {
+    if count as usize > MAX_UNLOCK_POINTS {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    let mut points = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let (offset, rest) = unpack_i64(data)?;
+        let (cumulative_basis_points, rest) = unpack_u16(rest)?;
+        points.push(UnlockPoint {
+            offset,
+            cumulative_basis_points,
+        });
+        data = rest;
+    }
+    Ok((points, data))
+}
+
+/// `count` recipient records of 34 bytes each: wallet followed by basis points.
+fn unpack_recipients(count: usize, mut data: &[u8]) -> Result<(Vec<RecipientData>, &[u8]), ProgramError> {
+    let mut recipients = Vec::with_capacity(count.min(MAX_RECIPIENTS));
+    for _ in 0..count {
+        let (wallet, rest) = unpack_pubkey(data)?;
+        let (basis_points, rest) = unpack_u16(rest)?;
+        recipients.push(RecipientData {
+            wallet,
+            basis_points,
+        });
+        data = rest;
+    }
+    Ok((recipients, data))
+}
+
+fn unpack_create_ata_flag(data: &[u8]) -> Result<bool, ProgramError> {
+    match data {
+        [] | [0] => Ok(false),
+        [1] => Ok(true),
+        _ => Err(InstructionError::InvalidInstructionData.into()),
+    }
+}
+
+fn unpack_pubkey(data: &[u8]) -> Result<(Pubkey, &[u8]), ProgramError> {
+    if data.len() < 32 {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    let (bytes, rest) = data.split_at(32);
+    let key = Pubkey::new_from_array(
+        bytes.try_into()
+            .map_err(|_| InstructionError::InvalidInstructionData)?
+    );
+    Ok((key, rest))
+}
+
+fn unpack_hash(data: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+    if data.len() < 32 {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    let (bytes, rest) = data.split_at(32);
+    let hash = bytes.try_into()
+        .map_err(|_| InstructionError::InvalidInstructionData)?;
+    Ok((hash, rest))
+}
+
+/// Proof length byte followed by that many sibling hashes
+fn unpack_proof(data: &[u8]) -> Result<(Vec<[u8; 32]>, &[u8]), ProgramError> {
+    let (&proof_len, mut rest) = data.split_first()
+        .ok_or(InstructionError::InvalidInstructionData)?;
+    if proof_len as usize > MAX_MERKLE_PROOF_LEN {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    let mut proof = Vec::with_capacity(proof_len as usize);
+    for _ in 0..proof_len {
+        let (node, after_node) = unpack_hash(rest)?;
+        proof.push(node);
+        rest = after_node;
+    }
+    Ok((proof, rest))
+}
+
+fn pack_proof(proof: &[[u8; 32]], buf: &mut Vec<u8>) {
+    buf.push(proof.len() as u8);
+    for node in proof {
+        buf.extend_from_slice(node);
+    }
+}
+
+/// Trailing timestamp of the query instructions; absent means "now"
+fn unpack_optional_timestamp(data: &[u8]) -> Result<Option<i64>, ProgramError> {
+    if data.is_empty() {
+        return Ok(None);
+    }
+    let (timestamp, rest) = unpack_i64(data)?;
+    if !rest.is_empty() {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    Ok(Some(timestamp))
+}
+
+fn unpack_u16(data: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+    if data.len() < 2 {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    let (bytes, rest) = data.split_at(2);
+    let value = u16::from_le_bytes(
+        bytes.try_into()
+            .map_err(|_| InstructionError::InvalidInstructionData)?
+    );
+    Ok((value, rest))
+}
+
+fn unpack_i64(data: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+    let (value, rest) = unpack_u64(data)?;
+    Ok((value as i64, rest))
+}
+
+fn unpack_u64(data: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if data.len() < 8 {
+        return Err(InstructionError::InvalidInstructionData.into());
+    }
+    let (bytes, rest) = data.split_at(8);
+    let value = u64::from_le_bytes(
+        bytes.try_into()
+            .map_err(|_| InstructionError::InvalidInstructionData)?
+    );
+    Ok((value, rest))
+}
+
 impl VestingInstruction {
     pub fn try_from(data: &[u8]) -> Result<Self, ProgramError> {
         if data.is_empty() {
@@ -49,8 +588,9 @@ impl VestingInstruction {
                     return Err(InstructionError::InvalidInstructionData.into());
                 }
                 
+                // Zero recipients is only valid in recipient-account and merkle modes
                 let recipient_count = data[1] as usize;
-                if recipient_count == 0 || recipient_count > MAX_RECIPIENTS {
+                if recipient_count > MAX_RECIPIENTS {
                     return Err(InstructionError::InvalidRecipientCount.into());
                 }
                 
@@ -71,44 +611,17 @@ impl VestingInstruction {
                         .map_err(|_| InstructionError::InvalidInstructionData)?
                 );
                 
-                let recipient_data_size = recipient_count.saturating_mul(34);
-                let expected_len = 28_usize.saturating_add(recipient_data_size);
-                
-                if recipient_data_size / 34 != recipient_count {
-                    return Err(InstructionError::InvalidInstructionData.into());
-                }
-                
-                if data.len() != expected_len {
-                    return Err(InstructionError::InvalidInstructionData.into());
-                }
-                
-                let mut recipients = Vec::with_capacity(recipient_count.min(MAX_RECIPIENTS));
-                let mut offset = 28; 
-                
-                for _ in 0..recipient_count {
-                    let wallet_bytes: [u8; 32] = data[offset..offset + 32]
-                        .try_into()
-                        .map_err(|_| InstructionError::InvalidInstructionData)?;
-                    let wallet = Pubkey::new_from_array(wallet_bytes);
-                    let basis_points = u16::from_le_bytes(
-                        data[offset + 32..offset + 34].try_into()
-                            .map_err(|_| InstructionError::InvalidInstructionData)?
-                    );
-                    
-                    recipients.push(RecipientData {
-                        wallet,
-                        basis_points,  
-                    });
-                    
-                    offset += 34;  
-                }
-                
+                let (recipients, rest) = unpack_recipients(recipient_count, &data[28..])?;
+
+                // More recipients may follow via AddRecipients; Fund requires exactly 100%
                 let total_basis_points: u32 = recipients.iter()
                     .map(|r| r.basis_points as u32)
                     .sum();
-                if total_basis_points != BASIS_POINTS_TOTAL as u32 {
+                if total_basis_points > BASIS_POINTS_TOTAL as u32 {
                     return Err(InstructionError::InvalidTotalPercentage.into());
                 }
+
+                let options = InitializeOptions::unpack(rest)?;
                 
                 Ok(VestingInstruction::InitializeVesting { 
                     recipients,
@@ -116,6 +629,7 @@ impl VestingInstruction {
                     vesting_period,
                     tge_basis_points,  
                     nonce,  
+                    options,
                 })
             }
             1 => {
@@ -130,9 +644,993 @@ impl VestingInstruction {
                 Ok(VestingInstruction::Fund(amount))
             }
             2 => {
-                Ok(VestingInstruction::Claim)
+                Ok(VestingInstruction::Claim {
+                    create_missing_atas: unpack_create_ata_flag(&data[1..])?,
+                })
+            }
+            3 => {
+                // ClaimOwn
+                Ok(VestingInstruction::ClaimOwn {
+                    create_missing_ata: unpack_create_ata_flag(&data[1..])?,
+                })
+            }
+            4 => {
+                // DistributeSubset: count followed by recipient indices
+                if data.len() < 2 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                let index_count = data[1] as usize;
+                if index_count == 0 || index_count > MAX_RECIPIENTS {
+                    return Err(InstructionError::InvalidRecipientCount.into());
+                }
+                if data.len() < 2 + index_count {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                let (recipient_indices, flag) = data[2..].split_at(index_count);
+                Ok(VestingInstruction::DistributeSubset {
+                    recipient_indices: recipient_indices.to_vec(),
+                    create_missing_atas: unpack_create_ata_flag(flag)?,
+                })
+            }
+            5 => {
+                // Revoke
+                if data.len() != 2 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(VestingInstruction::Revoke { recipient_index: data[1] })
+            }
+            6 => {
+                // RotateRecipientWallet
+                let (new_wallet, rest) = unpack_pubkey(&data[1..])?;
+                if !rest.is_empty() {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(VestingInstruction::RotateRecipientWallet { new_wallet })
+            }
+            7 => {
+                // Close
+                if data.len() != 1 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(VestingInstruction::Close)
+            }
+            8 => {
+                // SweepSurplus
+                if data.len() != 1 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(VestingInstruction::SweepSurplus)
+            }
+            9 => {
+                // AddRecipients
+                if data.len() < 2 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                let recipient_count = data[1] as usize;
+                if recipient_count == 0 {
+                    return Err(InstructionError::InvalidRecipientCount.into());
+                }
+                let (recipients, schedule_indices) = unpack_recipients(recipient_count, &data[2..])?;
+                if !schedule_indices.is_empty() && schedule_indices.len() != recipient_count {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(VestingInstruction::AddRecipients {
+                    recipients,
+                    schedule_indices: schedule_indices.to_vec(),
+                })
+            }
+            10 => {
+                // CreateRecipientAccount
+                let (wallet, rest) = unpack_pubkey(&data[1..])?;
+                let (basis_points, rest) = unpack_u16(rest)?;
+                let schedule_index = match rest {
+                    [] => 0,
+                    [index] => *index,
+                    _ => return Err(InstructionError::InvalidInstructionData.into()),
+                };
+                Ok(VestingInstruction::CreateRecipientAccount {
+                    wallet,
+                    basis_points,
+                    schedule_index,
+                })
+            }
+            11 => {
+                // ClaimRecipientAccount
+                Ok(VestingInstruction::ClaimRecipientAccount {
+                    create_missing_ata: unpack_create_ata_flag(&data[1..])?,
+                })
+            }
+            12 => {
+                // ClaimMerkle: allocation, proof length, proof hashes, optional flag
+                let (allocation, rest) = unpack_u64(&data[1..])?;
+                let (proof, rest) = unpack_proof(rest)?;
+                Ok(VestingInstruction::ClaimMerkle {
+                    allocation,
+                    proof,
+                    create_missing_ata: unpack_create_ata_flag(rest)?,
+                })
+            }
+            13 => {
+                // Migrate
+                if data.len() != 1 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(VestingInstruction::Migrate)
+            }
+            14 => {
+                // QueryClaimable: first index, count, optional timestamp
+                let (&first_index, rest) = data[1..].split_first()
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                let (&count, rest) = rest.split_first()
+                    .ok_or(InstructionError::InvalidInstructionData)?;
+                Ok(VestingInstruction::QueryClaimable {
+                    first_index,
+                    count,
+                    timestamp: unpack_optional_timestamp(rest)?,
+                })
+            }
+            15 | 16 => {
+                // Pause / Unpause
+                if data.len() != 1 {
+                    return Err(InstructionError::InvalidInstructionData.into());
+                }
+                Ok(if data[0] == 15 { VestingInstruction::Pause } else { VestingInstruction::Unpause })
+            }
+            17 => {
+                // QueryMerkleClaimable: wallet, allocation, proof, optional timestamp
+                let (wallet, rest) = unpack_pubkey(&data[1..])?;
+                let (allocation, rest) = unpack_u64(rest)?;
+                let (proof, rest) = unpack_proof(rest)?;
+                Ok(VestingInstruction::QueryMerkleClaimable {
+                    wallet,
+                    allocation,
+                    proof,
+                    timestamp: unpack_optional_timestamp(rest)?,
+                })
             }
             _ => Err(InstructionError::InvalidInstructionData.into()),
         }
     }
+
+    /// Serializes the instruction into the format read by `try_from`.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            VestingInstruction::InitializeVesting {
+                recipients,
+                cliff_period,
+                vesting_period,
+                tge_basis_points,
+                nonce,
+                options,
+            } => {
+                buf.push(0);
+                buf.push(recipients.len() as u8);
+                buf.extend_from_slice(&cliff_period.to_le_bytes());
+                buf.extend_from_slice(&vesting_period.to_le_bytes());
+                buf.extend_from_slice(&tge_basis_points.to_le_bytes());
+                buf.extend_from_slice(&nonce.to_le_bytes());
+                pack_recipients(recipients, &mut buf);
+                options.pack_into(&mut buf);
+            }
+            VestingInstruction::Fund(amount) => {
+                buf.push(1);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            VestingInstruction::Claim { create_missing_atas } => {
+                buf.push(2);
+                buf.push(*create_missing_atas as u8);
+            }
+            VestingInstruction::ClaimOwn { create_missing_ata } => {
+                buf.push(3);
+                buf.push(*create_missing_ata as u8);
+            }
+            VestingInstruction::DistributeSubset { recipient_indices, create_missing_atas } => {
+                buf.push(4);
+                buf.push(recipient_indices.len() as u8);
+                buf.extend_from_slice(recipient_indices);
+                buf.push(*create_missing_atas as u8);
+            }
+            VestingInstruction::Revoke { recipient_index } => {
+                buf.push(5);
+                buf.push(*recipient_index);
+            }
+            VestingInstruction::RotateRecipientWallet { new_wallet } => {
+                buf.push(6);
+                buf.extend_from_slice(new_wallet.as_ref());
+            }
+            VestingInstruction::Close => buf.push(7),
+            VestingInstruction::SweepSurplus => buf.push(8),
+            VestingInstruction::AddRecipients { recipients, schedule_indices } => {
+                buf.push(9);
+                buf.push(recipients.len() as u8);
+                pack_recipients(recipients, &mut buf);
+                buf.extend_from_slice(schedule_indices);
+            }
+            VestingInstruction::CreateRecipientAccount { wallet, basis_points, schedule_index } => {
+                buf.push(10);
+                buf.extend_from_slice(wallet.as_ref());
+                buf.extend_from_slice(&basis_points.to_le_bytes());
+                buf.push(*schedule_index);
+            }
+            VestingInstruction::ClaimRecipientAccount { create_missing_ata } => {
+                buf.push(11);
+                buf.push(*create_missing_ata as u8);
+            }
+            VestingInstruction::ClaimMerkle { allocation, proof, create_missing_ata } => {
+                buf.push(12);
+                buf.extend_from_slice(&allocation.to_le_bytes());
+                pack_proof(proof, &mut buf);
+                buf.push(*create_missing_ata as u8);
+            }
+            VestingInstruction::Migrate => buf.push(13),
+            VestingInstruction::QueryClaimable { first_index, count, timestamp } => {
+                buf.push(14);
+                buf.push(*first_index);
+                buf.push(*count);
+                if let Some(timestamp) = timestamp {
+                    buf.extend_from_slice(&timestamp.to_le_bytes());
+                }
+            }
+            VestingInstruction::Pause => buf.push(15),
+            VestingInstruction::Unpause => buf.push(16),
+            VestingInstruction::QueryMerkleClaimable { wallet, allocation, proof, timestamp } => {
+                buf.push(17);
+                buf.extend_from_slice(wallet.as_ref());
+                buf.extend_from_slice(&allocation.to_le_bytes());
+                pack_proof(proof, &mut buf);
+                if let Some(timestamp) = timestamp {
+                    buf.extend_from_slice(&timestamp.to_le_bytes());
+                }
+            }
+        }
+        buf
+    }
+}
+
+pub fn find_vesting_address(program_id: &Pubkey, initializer: &Pubkey, nonce: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vesting", initializer.as_ref(), &nonce.to_le_bytes()], program_id)
+}
+
+pub fn find_vault_address(program_id: &Pubkey, vesting: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", vesting.as_ref()], program_id)
+}
+
+pub fn find_vault_authority_address(program_id: &Pubkey, vesting: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"authority", vesting.as_ref()], program_id)
+}
+
+pub fn find_fee_pool_address(program_id: &Pubkey, vesting: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"fee_pool", vesting.as_ref()], program_id)
+}
+
+pub fn find_recipient_address(program_id: &Pubkey, vesting: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"recipient", vesting.as_ref(), wallet.as_ref()], program_id)
+}
+
+pub fn find_receipt_address(program_id: &Pubkey, vesting: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"receipt", vesting.as_ref(), wallet.as_ref()], program_id)
+}
+
+/// Creates an `InitializeVesting` instruction for the vesting PDA of `initializer` and `nonce`;
+/// fails when `options` do not pass `InitializeOptions::validate`.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_vesting(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    recipients: Vec<RecipientData>,
+    cliff_period: i64,
+    vesting_period: i64,
+    tge_basis_points: u16,
+    nonce: u64,
+    options: InitializeOptions,
+) -> Result<Instruction, ProgramError> {
+    options.validate()?;
+    let (vesting, _) = find_vesting_address(program_id, initializer, nonce);
+    let (vault, _) = find_vault_address(program_id, &vesting);
+    let data = VestingInstruction::InitializeVesting {
+        recipients,
+        cliff_period,
+        vesting_period,
+        tge_basis_points,
+        nonce,
+        options,
+    }
+    .pack();
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*initializer, true),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(sysvar::rent::ID, false),
+        ],
+        data,
+    })
+}
+
+/// Creates a `Fund` instruction moving `amount` from `source` into the vault.
+pub fn fund(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    source: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (vault, _) = find_vault_address(program_id, vesting);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*funder, true),
+            AccountMeta::new(*source, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: VestingInstruction::Fund(amount).pack(),
+    }
+}
+
+/// Creates a `Claim` paying every recipient; `recipient_wallets` must follow
+/// the recipient order of the vesting. `keeper_reward` adds the fee pool
+/// accounts a vesting with a keeper reward expects, and `ata_payer` makes
+/// the program create missing ATAs at that payer's expense.
+#[allow(clippy::too_many_arguments)]
+pub fn claim(
+    program_id: &Pubkey,
+    caller: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    recipient_wallets: &[Pubkey],
+    keeper_reward: bool,
+    ata_payer: Option<&Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: distribution_accounts(
+            program_id,
+            caller,
+            vesting,
+            mint,
+            token_program_id,
+            recipient_wallets,
+            keeper_reward,
+            ata_payer,
+        ),
+        data: VestingInstruction::Claim {
+            create_missing_atas: ata_payer.is_some(),
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `DistributeSubset` paying the recipients at the given
+/// `(index, wallet)` positions. Account options as in [`claim`].
+#[allow(clippy::too_many_arguments)]
+pub fn distribute_subset(
+    program_id: &Pubkey,
+    caller: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    recipients: &[(u8, Pubkey)],
+    keeper_reward: bool,
+    ata_payer: Option<&Pubkey>,
+) -> Instruction {
+    let wallets: Vec<Pubkey> = recipients.iter().map(|(_, wallet)| *wallet).collect();
+
+    Instruction {
+        program_id: *program_id,
+        accounts: distribution_accounts(
+            program_id,
+            caller,
+            vesting,
+            mint,
+            token_program_id,
+            &wallets,
+            keeper_reward,
+            ata_payer,
+        ),
+        data: VestingInstruction::DistributeSubset {
+            recipient_indices: recipients.iter().map(|(index, _)| *index).collect(),
+            create_missing_atas: ata_payer.is_some(),
+        }
+        .pack(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn distribution_accounts(
+    program_id: &Pubkey,
+    caller: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    recipient_wallets: &[Pubkey],
+    keeper_reward: bool,
+    ata_payer: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+
+    let mut accounts = vec![
+        AccountMeta::new(*caller, true),
+        AccountMeta::new(*vesting, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(sysvar::clock::ID, false),
+        AccountMeta::new_readonly(vault_authority, false),
+        AccountMeta::new_readonly(*mint, false),
+    ];
+    if keeper_reward {
+        let (fee_pool, _) = find_fee_pool_address(program_id, vesting);
+        accounts.push(AccountMeta::new(fee_pool, false));
+        accounts.push(AccountMeta::new_readonly(system_program::ID, false));
+    }
+    if let Some(payer) = ata_payer {
+        accounts.extend(ata_creation_accounts(payer));
+    }
+    for wallet in recipient_wallets {
+        if ata_payer.is_some() {
+            accounts.push(AccountMeta::new_readonly(*wallet, false));
+        }
+        accounts.push(AccountMeta::new(
+            get_associated_token_address_with_program_id(wallet, mint, token_program_id),
+            false,
+        ));
+    }
+    accounts
+}
+
+fn ata_creation_accounts(payer: &Pubkey) -> [AccountMeta; 3] {
+    [
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::ID, false),
+        AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+    ]
+}
+
+/// Creates a `ClaimOwn` signed by `recipient`, who also pays for a missing ATA.
+pub fn claim_own(
+    program_id: &Pubkey,
+    recipient: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    create_missing_ata: bool,
+) -> Instruction {
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*recipient, true),
+        AccountMeta::new(*vesting, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(
+            get_associated_token_address_with_program_id(recipient, mint, token_program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(sysvar::clock::ID, false),
+        AccountMeta::new_readonly(vault_authority, false),
+        AccountMeta::new_readonly(*mint, false),
+    ];
+    if create_missing_ata {
+        accounts.extend(ata_creation_accounts(recipient));
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: VestingInstruction::ClaimOwn { create_missing_ata }.pack(),
+    }
+}
+
+pub fn revoke(
+    program_id: &Pubkey,
+    revoker: &Pubkey,
+    vesting: &Pubkey,
+    clawback_account: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    recipient_index: u8,
+) -> Instruction {
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*revoker, true),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*clawback_account, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(sysvar::clock::ID, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: VestingInstruction::Revoke { recipient_index }.pack(),
+    }
+}
+
+pub fn rotate_recipient_wallet(
+    program_id: &Pubkey,
+    current_wallet: &Pubkey,
+    vesting: &Pubkey,
+    new_wallet: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*current_wallet, true),
+            AccountMeta::new(*vesting, false),
+        ],
+        data: VestingInstruction::RotateRecipientWallet { new_wallet: *new_wallet }.pack(),
+    }
+}
+
+/// `dust_account` is a token account of `rent_recipient` that receives what
+/// is left in the vault.
+pub fn close(
+    program_id: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    rent_recipient: &Pubkey,
+    dust_account: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+    let (fee_pool, _) = find_fee_pool_address(program_id, vesting);
+
+    let accounts = vec![
+        AccountMeta::new(*vesting, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(vault_authority, false),
+        AccountMeta::new(*rent_recipient, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new(*dust_account, false),
+        AccountMeta::new(fee_pool, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: VestingInstruction::Close.pack(),
+    }
+}
+
+pub fn sweep_surplus(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    vesting: &Pubkey,
+    destination: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+) -> Instruction {
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*initializer, true),
+            AccountMeta::new_readonly(*vesting, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: VestingInstruction::SweepSurplus.pack(),
+    }
+}
+
+pub fn add_recipients(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    vesting: &Pubkey,
+    recipients: Vec<RecipientData>,
+    schedule_indices: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*initializer, true),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: VestingInstruction::AddRecipients { recipients, schedule_indices }.pack(),
+    }
+}
+
+pub fn create_recipient_account(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    vesting: &Pubkey,
+    wallet: &Pubkey,
+    basis_points: u16,
+    schedule_index: u8,
+) -> Instruction {
+    let (recipient_account, _) = find_recipient_address(program_id, vesting, wallet);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*initializer, true),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new(recipient_account, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: VestingInstruction::CreateRecipientAccount {
+            wallet: *wallet,
+            basis_points,
+            schedule_index,
+        }
+        .pack(),
+    }
+}
+
+pub fn claim_recipient_account(
+    program_id: &Pubkey,
+    recipient: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    create_missing_ata: bool,
+) -> Instruction {
+    let (recipient_account, _) = find_recipient_address(program_id, vesting, recipient);
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*recipient, true),
+        AccountMeta::new(*vesting, false),
+        AccountMeta::new(recipient_account, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(
+            get_associated_token_address_with_program_id(recipient, mint, token_program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(sysvar::clock::ID, false),
+        AccountMeta::new_readonly(vault_authority, false),
+        AccountMeta::new_readonly(*mint, false),
+    ];
+    if create_missing_ata {
+        accounts.extend(ata_creation_accounts(recipient));
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: VestingInstruction::ClaimRecipientAccount { create_missing_ata }.pack(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn claim_merkle(
+    program_id: &Pubkey,
+    recipient: &Pubkey,
+    vesting: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    allocation: u64,
+    proof: Vec<[u8; 32]>,
+    create_missing_ata: bool,
+) -> Instruction {
+    let (receipt, _) = find_receipt_address(program_id, vesting, recipient);
+    let (vault, _) = find_vault_address(program_id, vesting);
+    let (vault_authority, _) = find_vault_authority_address(program_id, vesting);
+
+    let mut accounts = vec![
+        AccountMeta::new(*recipient, true),
+        AccountMeta::new(*vesting, false),
+        AccountMeta::new(receipt, false),
+        AccountMeta::new(vault, false),
+        AccountMeta::new(
+            get_associated_token_address_with_program_id(recipient, mint, token_program_id),
+            false,
+        ),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(sysvar::clock::ID, false),
+        AccountMeta::new_readonly(vault_authority, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+    if create_missing_ata {
+        accounts.extend(ata_creation_accounts(recipient));
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: VestingInstruction::ClaimMerkle {
+            allocation,
+            proof,
+            create_missing_ata,
+        }
+        .pack(),
+    }
+}
+
+pub fn migrate(program_id: &Pubkey, payer: &Pubkey, vesting: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data: VestingInstruction::Migrate.pack(),
+    }
+}
+
+/// Creates a `QueryClaimable` meant for `simulateTransaction`. Vestings
+/// without inline recipients are queried through `accounts`, their recipient
+/// accounts or claim receipts; pass an empty slice otherwise.
+pub fn query_claimable(
+    program_id: &Pubkey,
+    vesting: &Pubkey,
+    accounts: &[Pubkey],
+    first_index: u8,
+    count: u8,
+    timestamp: Option<i64>,
+) -> Instruction {
+    let mut metas = vec![AccountMeta::new_readonly(*vesting, false)];
+    metas.extend(accounts.iter().map(|account| AccountMeta::new_readonly(*account, false)));
+
+    Instruction {
+        program_id: *program_id,
+        accounts: metas,
+        data: VestingInstruction::QueryClaimable { first_index, count, timestamp }.pack(),
+    }
+}
+
+/// Creates a `QueryMerkleClaimable` meant for `simulateTransaction`; the
+/// proof may be empty once `wallet` has claimed and owns a receipt.
+pub fn query_merkle_claimable(
+    program_id: &Pubkey,
+    vesting: &Pubkey,
+    wallet: &Pubkey,
+    allocation: u64,
+    proof: Vec<[u8; 32]>,
+    timestamp: Option<i64>,
+) -> Instruction {
+    let (receipt, _) = find_receipt_address(program_id, vesting, wallet);
+
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*vesting, false),
+            AccountMeta::new_readonly(receipt, false),
+        ],
+        data: VestingInstruction::QueryMerkleClaimable {
+            wallet: *wallet,
+            allocation,
+            proof,
+            timestamp,
+        }
+        .pack(),
+    }
+}
+
+pub fn pause(program_id: &Pubkey, guardian: &Pubkey, vesting: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: guardian_accounts(guardian, vesting),
+        data: VestingInstruction::Pause.pack(),
+    }
+}
+
+pub fn unpause(program_id: &Pubkey, guardian: &Pubkey, vesting: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: guardian_accounts(guardian, vesting),
+        data: VestingInstruction::Unpause.pack(),
+    }
+}
+
+fn guardian_accounts(guardian: &Pubkey, vesting: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(*guardian, true),
+        AccountMeta::new(*vesting, false),
+        AccountMeta::new_readonly(sysvar::clock::ID, false),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(instruction: VestingInstruction) {
+        let packed = instruction.pack();
+        let unpacked = VestingInstruction::try_from(&packed).unwrap();
+        assert_eq!(unpacked, instruction);
+        assert_eq!(unpacked.pack(), packed);
+    }
+
+    #[test]
+    fn test_pack_unpack_round_trip() {
+        let recipients = vec![
+            RecipientData { wallet: Pubkey::new_unique(), basis_points: 6000 },
+            RecipientData { wallet: Pubkey::new_unique(), basis_points: 4000 },
+        ];
+
+        round_trip(VestingInstruction::InitializeVesting {
+            recipients: recipients.clone(),
+            cliff_period: 60,
+            vesting_period: 3600,
+            tge_basis_points: 1000,
+            nonce: 7,
+            options: InitializeOptions::default(),
+        });
+
+        let mut extra_schedule = VestingSchedule {
+            unlock_point_count: 2,
+            ..VestingSchedule::default()
+        };
+        extra_schedule.unlock_points[0] = UnlockPoint { offset: 10, cumulative_basis_points: 2500 };
+        extra_schedule.unlock_points[1] = UnlockPoint { offset: 20, cumulative_basis_points: 10000 };
+        round_trip(VestingInstruction::InitializeVesting {
+            recipients,
+            cliff_period: 0,
+            vesting_period: 0,
+            tge_basis_points: 0,
+            nonce: u64::MAX,
+            options: InitializeOptions {
+                permissionless_distribution: true,
+                keeper_reward_lamports: 5000,
+                revocable: true,
+                revoker: Pubkey::new_unique(),
+                clawback_account: Pubkey::new_unique(),
+                rent_recipient: Some(Pubkey::new_unique()),
+                funder: Some(Pubkey::new_unique()),
+                expected_amount: 1_000_000,
+                start_time: Some(1_700_000_000),
+                unlock_points: vec![
+                    UnlockPoint { offset: 0, cumulative_basis_points: 5000 },
+                    UnlockPoint { offset: 100, cumulative_basis_points: 10000 },
+                ],
+                step_period: 30,
+                additional_schedules: vec![extra_schedule],
+                recipient_schedule_indices: vec![0, 1],
+                recipient_accounts: true,
+                merkle_root: Some([3; 32]),
+                merkle_total_allocation: 42,
+                guardian: Some(Pubkey::new_unique()),
+            },
+        });
+
+        round_trip(VestingInstruction::Fund(123));
+        round_trip(VestingInstruction::Claim { create_missing_atas: true });
+        round_trip(VestingInstruction::ClaimOwn { create_missing_ata: false });
+        round_trip(VestingInstruction::DistributeSubset {
+            recipient_indices: vec![2, 0],
+            create_missing_atas: true,
+        });
+        round_trip(VestingInstruction::Revoke { recipient_index: 1 });
+        round_trip(VestingInstruction::RotateRecipientWallet { new_wallet: Pubkey::new_unique() });
+        round_trip(VestingInstruction::Close);
+        round_trip(VestingInstruction::SweepSurplus);
+        round_trip(VestingInstruction::AddRecipients {
+            recipients: vec![RecipientData { wallet: Pubkey::new_unique(), basis_points: 1 }],
+            schedule_indices: vec![3],
+        });
+        round_trip(VestingInstruction::CreateRecipientAccount {
+            wallet: Pubkey::new_unique(),
+            basis_points: 2500,
+            schedule_index: 2,
+        });
+        round_trip(VestingInstruction::ClaimRecipientAccount { create_missing_ata: true });
+        round_trip(VestingInstruction::ClaimMerkle {
+            allocation: 500,
+            proof: vec![[1; 32], [2; 32]],
+            create_missing_ata: false,
+        });
+        round_trip(VestingInstruction::Migrate);
+        round_trip(VestingInstruction::QueryClaimable { first_index: 3, count: 0, timestamp: None });
+        round_trip(VestingInstruction::QueryClaimable {
+            first_index: 0,
+            count: 1,
+            timestamp: Some(-1),
+        });
+        round_trip(VestingInstruction::Pause);
+        round_trip(VestingInstruction::Unpause);
+        round_trip(VestingInstruction::QueryMerkleClaimable {
+            wallet: Pubkey::new_unique(),
+            allocation: 500,
+            proof: vec![[3; 32]],
+            timestamp: None,
+        });
+        round_trip(VestingInstruction::QueryMerkleClaimable {
+            wallet: Pubkey::new_unique(),
+            allocation: 0,
+            proof: vec![],
+            timestamp: Some(1_700_000_000),
+        });
+    }
+
+    #[test]
+    fn test_inconsistent_options_are_rejected() {
+        let build = |options: InitializeOptions| {
+            initialize_vesting(
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &spl_token::ID,
+                vec![RecipientData { wallet: Pubkey::new_unique(), basis_points: 10000 }],
+                0,
+                600,
+                0,
+                1,
+                options,
+            )
+        };
+        let inconsistent: Result<Instruction, ProgramError> = Err(InstructionError::InconsistentOptions.into());
+
+        assert_eq!(build(InitializeOptions { expected_amount: 500, ..InitializeOptions::default() }), inconsistent);
+        assert_eq!(build(InitializeOptions { keeper_reward_lamports: 5000, ..InitializeOptions::default() }), inconsistent);
+        assert_eq!(
+            build(InitializeOptions { clawback_account: Pubkey::new_unique(), ..InitializeOptions::default() }),
+            inconsistent
+        );
+        assert_eq!(build(InitializeOptions { merkle_total_allocation: 1, ..InitializeOptions::default() }), inconsistent);
+
+        // The same settings encode once their enabling option is set
+        assert!(build(InitializeOptions {
+            funder: Some(Pubkey::new_unique()),
+            expected_amount: 500,
+            permissionless_distribution: true,
+            keeper_reward_lamports: 5000,
+            ..InitializeOptions::default()
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_legacy_initialize_layout() {
+        // 28-byte header and 34-byte recipient records as built by tests/1-initialize.js
+        let wallet = Pubkey::new_unique();
+        let mut data = vec![0, 1];
+        data.extend_from_slice(&0i64.to_le_bytes());
+        data.extend_from_slice(&600i64.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&9u64.to_le_bytes());
+        data.extend_from_slice(wallet.as_ref());
+        data.extend_from_slice(&10000u16.to_le_bytes());
+
+        assert_eq!(
+            initialize_vesting(
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &spl_token::ID,
+                vec![RecipientData { wallet, basis_points: 10000 }],
+                0,
+                600,
+                0,
+                9,
+                InitializeOptions::default(),
+            )
+            .unwrap()
+            .data,
+            data
+        );
+    }
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
-mod instruction;
+pub mod instruction;
 pub mod processor;
-mod state;
+pub mod state;
 pub mod errors;
-
-use solana_program::entrypoint;
+pub mod cpi;
+pub mod events;
 
 pub use crate::processor::process_instruction;
 
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
@@ -1,29 +1,54 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
+    hash::hashv,
     entrypoint::ProgramResult,
-    program::{invoke_signed, invoke},
+    msg,
+    program::{invoke_signed, invoke, set_return_data},
+    program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
     sysvar::Sysvar,
     program_pack::Pack,
 };
-use spl_token::{
-    instruction::{initialize_account2, transfer},
-    state::{Account as TokenAccount, Mint},
+use spl_token_2022::{
+    extension::{
+        default_account_state::DefaultAccountState,
+        transfer_fee::{instruction::harvest_withheld_tokens_to_mint, TransferFeeAmount},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::{close_account, initialize_account3, transfer_checked},
+    state::{Account as TokenAccount, AccountState, Mint},
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
 };
-use spl_associated_token_account::get_associated_token_address;
 use std::collections::HashSet;
 
-use crate::instruction::{VestingInstruction, RecipientData};
-use crate::state::{VestingAccount, Recipient, VestingSchedule, MAX_RECIPIENTS, BASIS_POINTS_TOTAL};
+use crate::instruction::{
+    VestingInstruction, RecipientData, InitializeOptions, ClaimableAmounts, ClaimableQuery,
+    MAX_QUERY_ENTRIES,
+};
+use crate::state::{
+    VestingAccount, VestingHeader, PodRecipient, Recipient, RecipientAccount, ClaimReceipt,
+    VestingSchedule, UnlockPoint, CURRENT_VERSION,
+    MAX_RECIPIENTS, MAX_SCHEDULES, MAX_UNLOCK_POINTS, BASIS_POINTS_TOTAL,
+};
 use crate::errors::VestingError;
+use crate::events::VestingEvent;
 
 
 const MAX_VESTING_DURATION: i64 = 4 * 365 * 24 * 60 * 60; 
 const MAX_CLIFF_DURATION: i64 = 365 * 24 * 60 * 60;        
 const DISTRIBUTION_COOLDOWN: i64 = 60;                     
+const MAX_KEEPER_REWARD_LAMPORTS: u64 = 5_000_000;
+const MIN_KEEPER_REWARD_BASIS_POINTS: u16 = 10;
+const KEEPER_REWARD_INTERVAL: i64 = 60 * 60;
+const MAX_START_DELAY: i64 = 365 * 24 * 60 * 60;
+const MAX_PAUSE_DURATION: i64 = 7 * 24 * 60 * 60;
+const MAX_TOTAL_PAUSE_DURATION: i64 = 30 * 24 * 60 * 60;
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -38,7 +63,8 @@ pub fn process_instruction(
             cliff_period,
             vesting_period,
             tge_basis_points,
-            nonce
+            nonce,
+            options,
         } => {
             process_initialize_vesting(
                 program_id,
@@ -47,18 +73,65 @@ pub fn process_instruction(
                 cliff_period,
                 vesting_period,
                 tge_basis_points,
-                nonce
+                nonce,
+                options,
             )
         }
         VestingInstruction::Fund(amount) => {
             process_fund(program_id, accounts, amount)
         }
-        VestingInstruction::Claim => {
-            process_distribute_to_all(program_id, accounts)
+        VestingInstruction::Claim { create_missing_atas } => {
+            process_distribute(program_id, accounts, None, create_missing_atas)
+        }
+        VestingInstruction::DistributeSubset { recipient_indices, create_missing_atas } => {
+            process_distribute(program_id, accounts, Some(&recipient_indices), create_missing_atas)
+        }
+        VestingInstruction::ClaimOwn { create_missing_ata } => {
+            process_claim_own(program_id, accounts, create_missing_ata)
+        }
+        VestingInstruction::Revoke { recipient_index } => {
+            process_revoke(program_id, accounts, recipient_index)
+        }
+        VestingInstruction::RotateRecipientWallet { new_wallet } => {
+            process_rotate_recipient_wallet(program_id, accounts, new_wallet)
+        }
+        VestingInstruction::Close => {
+            process_close(program_id, accounts)
+        }
+        VestingInstruction::SweepSurplus => {
+            process_sweep_surplus(program_id, accounts)
+        }
+        VestingInstruction::AddRecipients { recipients, schedule_indices } => {
+            process_add_recipients(program_id, accounts, recipients, schedule_indices)
+        }
+        VestingInstruction::CreateRecipientAccount { wallet, basis_points, schedule_index } => {
+            process_create_recipient_account(program_id, accounts, wallet, basis_points, schedule_index)
+        }
+        VestingInstruction::ClaimRecipientAccount { create_missing_ata } => {
+            process_claim_recipient_account(program_id, accounts, create_missing_ata)
+        }
+        VestingInstruction::ClaimMerkle { allocation, proof, create_missing_ata } => {
+            process_claim_merkle(program_id, accounts, allocation, &proof, create_missing_ata)
+        }
+        VestingInstruction::Migrate => {
+            process_migrate(program_id, accounts)
+        }
+        VestingInstruction::QueryClaimable { first_index, count, timestamp } => {
+            process_query_claimable(program_id, accounts, first_index, count, timestamp)
+        }
+        VestingInstruction::Pause => {
+            process_set_paused(program_id, accounts, true)
+        }
+        VestingInstruction::Unpause => {
+            process_set_paused(program_id, accounts, false)
+        }
+        VestingInstruction::QueryMerkleClaimable { wallet, allocation, proof, timestamp } => {
+            process_query_merkle_claimable(program_id, accounts, &wallet, allocation, &proof, timestamp)
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_initialize_vesting(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -67,6 +140,7 @@ fn process_initialize_vesting(
     vesting_period: i64,
     tge_basis_points: u16,
     nonce: u64,
+    options: InitializeOptions,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let initializer = next_account_info(account_info_iter)?;
@@ -97,7 +171,7 @@ fn process_initialize_vesting(
         return Err(VestingError::InvalidSystemProgram.into());
     }
     
-    if token_program.key != &spl_token::ID {
+    if !is_token_program(token_program.key) {
         return Err(VestingError::InvalidTokenProgram.into());
     }
     
@@ -105,48 +179,98 @@ fn process_initialize_vesting(
         return Err(VestingError::InvalidRentSysvar.into());
     }
 
-    if mint.owner != &spl_token::ID {
+    if mint.owner != token_program.key {
         return Err(VestingError::InvalidMint.into());
     }
-    let _mint_info = Mint::unpack(&mint.data.borrow())?;
+    let vault_len = {
+        let mint_data = mint.data.borrow();
+        let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+        let mint_extensions = mint_state.get_extension_types()?;
+        validate_mint_extensions(&mint_state, &mint_extensions)?;
+        let account_extensions =
+            ExtensionType::get_required_init_account_extensions(&mint_extensions);
+        ExtensionType::try_calculate_account_len::<TokenAccount>(&account_extensions)?
+    };
 
-    if vesting_period > MAX_VESTING_DURATION {
-        return Err(VestingError::VestingDurationTooLong.into());
+    let mut schedule = VestingSchedule {
+        cliff_period,
+        vesting_period,
+        tge_basis_points,
+        unlock_point_count: options.unlock_points.len() as u8,
+        step_period: options.step_period,
+        ..VestingSchedule::default()
+    };
+    for (slot, point) in schedule.unlock_points.iter_mut().zip(options.unlock_points.iter()) {
+        *slot = *point;
     }
+    validate_schedule(&schedule)?;
 
-    if cliff_period > MAX_CLIFF_DURATION {
-        return Err(VestingError::CliffDurationTooLong.into());
+    let mut schedules = [VestingSchedule::default(); MAX_SCHEDULES];
+    schedules[0] = schedule;
+    for (slot, additional) in schedules[1..].iter_mut().zip(options.additional_schedules.iter()) {
+        validate_schedule(additional)?;
+        *slot = *additional;
     }
+    let schedule_count = 1 + options.additional_schedules.len();
 
-    if cliff_period >= vesting_period {
-        return Err(VestingError::CliffExceedsVesting.into());
+    if let Some(merkle_root) = options.merkle_root {
+        if options.recipient_accounts
+            || merkle_root == [0; 32]
+            || options.merkle_total_allocation == 0
+        {
+            return Err(VestingError::InvalidMerkleRoot.into());
+        }
     }
 
-    if tge_basis_points > BASIS_POINTS_TOTAL {
-        return Err(VestingError::InvalidPercentage.into());
+    if options.recipient_accounts || options.merkle_root.is_some() {
+        if !recipients.is_empty() {
+            return Err(VestingError::InvalidRecipientCount.into());
+        }
+    } else if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(VestingError::InvalidRecipientCount.into());
     }
 
-    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
-        return Err(VestingError::InvalidRecipientCount.into());
+    if !options.recipient_schedule_indices.is_empty() {
+        if options.recipient_schedule_indices.len() != recipients.len() {
+            return Err(VestingError::InvalidScheduleIndex.into());
+        }
+        if options.recipient_schedule_indices.iter().any(|&index| index as usize >= schedule_count) {
+            return Err(VestingError::InvalidScheduleIndex.into());
+        }
     }
     
-    let total_basis_points: u32 = recipients.iter()
-        .map(|r| r.basis_points as u32)
-        .sum();
-    if total_basis_points != BASIS_POINTS_TOTAL as u32 {
-        return Err(VestingError::InvalidTotalPercentage.into());
+    validate_new_recipients(&[], &recipients)?;
+
+    if options.keeper_reward_lamports > 0 && !options.permissionless_distribution {
+        return Err(VestingError::InvalidKeeperReward.into());
     }
 
-    let mut seen_wallets = HashSet::new();
-    for recipient in &recipients {
-        if recipient.wallet == Pubkey::default() {
-            return Err(VestingError::InvalidRecipientWallet.into());
-        }
-        if !seen_wallets.insert(recipient.wallet) {
-            return Err(VestingError::DuplicateRecipient.into());
+    if options.keeper_reward_lamports > MAX_KEEPER_REWARD_LAMPORTS {
+        return Err(VestingError::InvalidKeeperReward.into());
+    }
+
+    if options.rent_recipient == Some(Pubkey::default()) {
+        return Err(VestingError::InvalidRentRecipient.into());
+    }
+
+    if options.funder == Some(Pubkey::default()) {
+        return Err(VestingError::UnauthorizedFunder.into());
+    }
+
+    if options.guardian == Some(Pubkey::default()) {
+        return Err(VestingError::UnauthorizedAccess.into());
+    }
+
+    if let Some(start_time) = options.start_time {
+        validate_start_time(start_time, Clock::get()?.unix_timestamp)?;
+    }
+
+    if options.revocable {
+        if options.revoker == Pubkey::default() {
+            return Err(VestingError::UnauthorizedAccess.into());
         }
-        if recipient.basis_points == 0 {
-            return Err(VestingError::ZeroPercentage.into());
+        if options.clawback_account == Pubkey::default() {
+            return Err(VestingError::InvalidClawbackAccount.into());
         }
     }
 
@@ -160,14 +284,15 @@ fn process_initialize_vesting(
     }
 
     let rent = Rent::from_account_info(rent_info)?;
-    let vesting_lamports = rent.minimum_balance(VestingAccount::LEN);
+    let vesting_len = VestingAccount::account_len(recipients.len());
+    let vesting_lamports = rent.minimum_balance(vesting_len);
     
     invoke_signed(
         &system_instruction::create_account(
             initializer.key,
             vesting_pda.key,
             vesting_lamports,
-            VestingAccount::LEN as u64,
+            vesting_len as u64,
             program_id,
         ),
         &[
@@ -178,14 +303,14 @@ fn process_initialize_vesting(
         &[&[b"vesting", initializer.key.as_ref(), &nonce.to_le_bytes(), &[vesting_bump]]],
     )?;
 
-    let token_rent = rent.minimum_balance(TokenAccount::LEN);
+    let token_rent = rent.minimum_balance(vault_len);
     
     invoke_signed(
         &system_instruction::create_account(
             initializer.key,
             vault_pda.key,
             token_rent,
-            TokenAccount::LEN as u64,
+            vault_len as u64,
             token_program.key,
         ),
         &[
@@ -200,7 +325,7 @@ fn process_initialize_vesting(
         Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
     
     invoke_signed(
-        &initialize_account2(
+        &initialize_account3(
             token_program.key,
             vault_pda.key,
             mint.key,
@@ -209,42 +334,66 @@ fn process_initialize_vesting(
         &[
             vault_pda.clone(),
             mint.clone(),
-            rent_info.clone(),
             token_program.clone(),
         ],
         &[&[b"authority", vesting_pda.key.as_ref(), &[auth_bump]]],
     )?;
 
-    let mut fixed_recipients = [Recipient::default(); MAX_RECIPIENTS];
-    for (i, recipient) in recipients.iter().enumerate() {
-        if i >= MAX_RECIPIENTS { break; }
-        fixed_recipients[i] = Recipient {
+    let new_recipients = recipients.iter().enumerate()
+        .map(|(i, recipient)| Recipient {
             wallet: recipient.wallet,
             basis_points: recipient.basis_points, 
             claimed_amount: 0,
             last_claim_time: 0,
-        };
-    }
+            revoked_at: 0,
+            schedule_index: options.recipient_schedule_indices.get(i).copied().unwrap_or(0),
+        })
+        .collect();
 
     let vesting = VestingAccount {
+        version: CURRENT_VERSION,
         is_initialized: true,
         initializer: *initializer.key,
         mint: *mint.key,
         vault: *vault_pda.key,
-        start_time: 0, 
+        start_time: options.start_time.unwrap_or(0), 
         total_amount: 0, 
-        schedule: VestingSchedule {
-            cliff_period,
-            vesting_period,
-            tge_basis_points,
-        },
-        recipients: fixed_recipients,
+        schedules,
+        schedule_count: schedule_count as u8,
+        recipients: new_recipients,
         recipient_count: recipients.len() as u8,
         is_finalized: false,
         last_distribution_time: 0, 
+        permissionless_distribution: options.permissionless_distribution,
+        keeper_reward_lamports: options.keeper_reward_lamports,
+        last_keeper_reward_time: 0,
+        revocable: options.revocable,
+        revoker: options.revoker,
+        clawback_account: options.clawback_account,
+        rent_recipient: options.rent_recipient.unwrap_or(*initializer.key),
+        funder: options.funder.unwrap_or(*initializer.key),
+        expected_amount: options.expected_amount,
+        recipient_accounts: options.recipient_accounts,
+        allocated_basis_points: 0,
+        merkle_root: options.merkle_root.unwrap_or_default(),
+        merkle_total_allocation: options.merkle_total_allocation,
+        guardian: options.guardian.unwrap_or_default(),
+        paused_until: 0,
+        pause_duration_used: 0,
+        settled_basis_points: 0,
+        merkle_claimed_amount: 0,
     };
 
     vesting.pack_into_slice(&mut vesting_pda.data.borrow_mut());
+
+    VestingEvent::VestingInitialized {
+        vesting: *vesting_pda.key,
+        initializer: vesting.initializer,
+        mint: vesting.mint,
+        recipient_count: vesting.recipient_count,
+        start_time: vesting.start_time,
+    }
+    .emit();
     
     Ok(())
 }
@@ -261,18 +410,23 @@ fn process_fund(
     let vesting_pda = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
     let clock = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
 
     if !funder.is_signer {
         return Err(VestingError::NotSigner.into());
     }
 
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+
     if vesting_pda.owner != program_id {
         return Err(VestingError::InvalidAccountOwner.into());
     }
-    if vault_pda.owner != &spl_token::ID {
+    if vault_pda.owner != token_program.key {
         return Err(VestingError::InvalidAccountOwner.into());
     }
-    if source_token.owner != &spl_token::ID {
+    if source_token.owner != token_program.key {
         return Err(VestingError::InvalidAccountOwner.into());
     }
 
@@ -280,35 +434,56 @@ fn process_fund(
         return Err(VestingError::InvalidClockSysvar.into());
     }
 
-    if token_program.key != &spl_token::ID {
-        return Err(VestingError::InvalidTokenProgram.into());
-    }
-
     if amount == 0 {
         return Err(VestingError::InvalidAmount.into());
     }
 
-    let mut vesting = VestingAccount::unpack_from_slice(&vesting_pda.data.borrow())?;
-    
-    if !vesting.is_initialized {
-        return Err(VestingError::NotInitialized.into());
-    }
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, recipients) = load_vesting_mut(&mut vesting_data)?;
     
-    if vesting.start_time != 0 {
+    if u64::from(vesting.total_amount) != 0 {
         return Err(VestingError::AlreadyFunded.into());
     }
 
-    if vesting.is_finalized {
+    if bool::from(vesting.is_finalized) {
         return Err(VestingError::VestingFinalized.into());
     }
 
+    if vesting.funder != *funder.key {
+        return Err(VestingError::UnauthorizedFunder.into());
+    }
+
+    // Merkle allocations are absolute amounts and are checked after the transfer
+    if !vesting.is_merkle() {
+        let total_basis_points: u32 = if bool::from(vesting.recipient_accounts) {
+            u16::from(vesting.allocated_basis_points) as u32
+        } else {
+            recipients.iter()
+                .map(|r| u16::from(r.basis_points) as u32)
+                .sum()
+        };
+        if total_basis_points != BASIS_POINTS_TOTAL as u32 {
+            return Err(VestingError::InvalidTotalPercentage.into());
+        }
+    }
+
+    let expected_amount = u64::from(vesting.expected_amount);
+    if expected_amount != 0 && amount != expected_amount {
+        return Err(VestingError::InvalidAmount.into());
+    }
+
     let (vault_address, _) = 
         Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
     if vault_pda.key != &vault_address {
         return Err(VestingError::InvalidPDA.into());
     }
 
-    let source_account = TokenAccount::unpack(&source_token.data.borrow())?;
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let source_account = unpack_token_account(source_token)?;
     if source_account.owner != *funder.key {
         return Err(VestingError::InvalidTokenOwner.into());
     }
@@ -321,87 +496,147 @@ fn process_fund(
         return Err(VestingError::InsufficientFunds.into());
     }
 
+    // Token-2022 transfer fees are withheld from the amount the vault receives
+    let vault_balance_before = unpack_token_account(vault_pda)?.amount;
+
     invoke(
-        &transfer(
+        &transfer_checked(
             token_program.key,
             source_token.key,
+            mint.key,
             vault_pda.key,
             funder.key,
             &[],
             amount,
+            decimals,
         )?,
         &[
             source_token.clone(),
+            mint.clone(),
             vault_pda.clone(),
             funder.clone(),
             token_program.clone(),
         ],
     )?;
 
-    let clock = Clock::from_account_info(clock)?;
-    vesting.start_time = clock.unix_timestamp;
-    vesting.total_amount = amount;
-    vesting.is_finalized = true;
-    vesting.pack_into_slice(&mut vesting_pda.data.borrow_mut());
+    let vault_balance_after = unpack_token_account(vault_pda)?.amount;
+    let received = vault_balance_after
+        .checked_sub(vault_balance_before)
+        .ok_or(VestingError::Underflow)?;
+    if received == 0 || received < u64::from(vesting.merkle_total_allocation) {
+        return Err(VestingError::InvalidAmount.into());
+    }
+
+    // Without a scheduled start the funding moment becomes the TGE
+    if i64::from(vesting.start_time) == 0 {
+        let clock = Clock::from_account_info(clock)?;
+        vesting.start_time = clock.unix_timestamp.into();
+    }
+    vesting.total_amount = received.into();
+    vesting.is_finalized = true.into();
+
+    VestingEvent::Funded {
+        vesting: *vesting_pda.key,
+        funder: *funder.key,
+        amount: received,
+        start_time: vesting.start_time.into(),
+    }
+    .emit();
     
     Ok(())
 }
 
-fn process_distribute_to_all(
+/// Pays every recipient (`recipient_indices == None`) or only the listed ones.
+/// Recipient ATAs follow in the same order as the recipients they pay; with
+/// `create_missing_atas` the ATA creation accounts come first and every ATA
+/// is preceded by its wallet.
+fn process_distribute(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
+    recipient_indices: Option<&[u8]>,
+    create_missing_atas: bool,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    let initializer = next_account_info(account_info_iter)?;
+    let caller = next_account_info(account_info_iter)?;
     let vesting_pda = next_account_info(account_info_iter)?;
     let vault_pda = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
     let clock = next_account_info(account_info_iter)?;
     let vault_authority = next_account_info(account_info_iter)?;
-    
-    let recipient_atas: Vec<&AccountInfo> = account_info_iter.collect();
+    let mint = next_account_info(account_info_iter)?;
 
-    if !initializer.is_signer {
+    if !caller.is_signer {
         return Err(VestingError::NotSigner.into());
     }
 
     if vesting_pda.owner != program_id {
         return Err(VestingError::InvalidAccountOwner.into());
     }
-    if vault_pda.owner != &spl_token::ID {
-        return Err(VestingError::InvalidAccountOwner.into());
-    }
-    if token_program.key != &spl_token::ID {
+    if !is_token_program(token_program.key) {
         return Err(VestingError::InvalidTokenProgram.into());
     }
+    if vault_pda.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
     if clock.key != &solana_program::sysvar::clock::ID {
         return Err(VestingError::InvalidClockSysvar.into());
     }
 
-    let mut vesting = VestingAccount::unpack_from_slice(&vesting_pda.data.borrow())?;
-    
-    if !vesting.is_initialized {
-        return Err(VestingError::NotInitialized.into());
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, recipients) = load_vesting_mut(&mut vesting_data)?;
+
+    if !vesting.uses_inline_recipients() {
+        return Err(VestingError::UnsupportedVestingMode.into());
     }
     
-    if vesting.initializer != *initializer.key {
+    if !bool::from(vesting.permissionless_distribution) && vesting.initializer != *caller.key {
         return Err(VestingError::NotInitializer.into());
     }
+
+    // Fee pool accounts are only part of the layout when a keeper reward is configured
+    let keeper_reward_lamports = u64::from(vesting.keeper_reward_lamports);
+    let keeper_accounts = if keeper_reward_lamports > 0 {
+        let fee_pool = next_account_info(account_info_iter)?;
+        let system_program = next_account_info(account_info_iter)?;
+        Some((fee_pool, system_program))
+    } else {
+        None
+    };
+
+    let ata_creation = if create_missing_atas {
+        Some(AtaCreationAccounts::next(account_info_iter)?)
+    } else {
+        None
+    };
+
+    let recipient_accounts: Vec<(Option<&AccountInfo>, &AccountInfo)> = if create_missing_atas {
+        let pairs = account_info_iter.as_slice().chunks_exact(2);
+        if !pairs.remainder().is_empty() {
+            return Err(VestingError::InvalidATACount.into());
+        }
+        pairs.map(|pair| (Some(&pair[0]), &pair[1])).collect()
+    } else {
+        account_info_iter.map(|ata| (None, ata)).collect()
+    };
     
-    
-    if vesting.start_time == 0 {
+    if u64::from(vesting.total_amount) == 0 {
         return Err(VestingError::NotFunded.into());
     }
 
-    if !vesting.is_finalized {
+    if !bool::from(vesting.is_finalized) {
         return Err(VestingError::NotFinalized.into());
     }
 
     let clock = Clock::from_account_info(clock)?;
     let current_time = clock.unix_timestamp;
+
+    if vesting.is_paused(current_time) {
+        return Err(VestingError::VestingPaused.into());
+    }
     
-    if vesting.last_distribution_time > 0 {
-        let time_since_last = current_time - vesting.last_distribution_time;
+    let last_distribution_time = i64::from(vesting.last_distribution_time);
+    if last_distribution_time > 0 {
+        let time_since_last = current_time - last_distribution_time;
         if time_since_last < DISTRIBUTION_COOLDOWN {
             return Err(VestingError::DistributionCooldown.into());
         }
@@ -420,11 +655,37 @@ fn process_distribute_to_all(
         return Err(VestingError::InvalidAuthority.into());
     }
 
-    if recipient_atas.len() != vesting.recipient_count as usize {
+    let selected: Vec<usize> = match recipient_indices {
+        None => (0..recipients.len()).collect(),
+        Some(indices) => {
+            let mut seen = [false; MAX_RECIPIENTS];
+            let mut selected = Vec::with_capacity(indices.len());
+            for &index in indices {
+                let index = index as usize;
+                if index >= recipients.len() || seen[index] {
+                    return Err(VestingError::InvalidRecipientIndex.into());
+                }
+                seen[index] = true;
+                selected.push(index);
+            }
+            selected
+        }
+    };
+
+    if recipient_accounts.len() != selected.len() {
         return Err(VestingError::InvalidATACount.into());
     }
 
-    let vault_account = TokenAccount::unpack(&vault_pda.data.borrow())?;
+    if let Some(ata_creation) = &ata_creation {
+        ata_creation.validate()?;
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let vault_account = unpack_token_account(vault_pda)?;
     if vault_account.owner != vault_authority_key {
         return Err(VestingError::InvalidTokenOwner.into());
     }
@@ -434,22 +695,16 @@ fn process_distribute_to_all(
 
     let mut total_distributed = 0u64;
 
-    let mut transfer_instructions: Vec<(usize, u64, &AccountInfo)> = Vec::with_capacity(MAX_RECIPIENTS);
-    let mut pending_updates: Vec<(usize, u64, i64)> = Vec::with_capacity(MAX_RECIPIENTS);
+    let mut transfer_instructions: Vec<(usize, u64, &AccountInfo)> = Vec::with_capacity(selected.len());
     
-    for (i, recipient) in vesting.recipients.iter().take(vesting.recipient_count as usize).enumerate() {
+    for (&i, &(recipient_wallet, recipient_ata)) in selected.iter().zip(recipient_accounts.iter()) {
+        let recipient = Recipient::from(&recipients[i]);
         
         if recipient.wallet == Pubkey::default() || recipient.basis_points == 0 {
             continue;
         }
 
-        let recipient_total = (vesting.total_amount as u128 * recipient.basis_points as u128 / BASIS_POINTS_TOTAL as u128) as u64;
-        let vested_amount = calculate_vested_amount(
-            recipient_total,
-            current_time,
-            vesting.start_time,
-            &vesting.schedule,
-        );
+        let vested_amount = calculate_recipient_vested(vesting, &recipient, current_time)?;
         
         let claimable = vested_amount.saturating_sub(recipient.claimed_amount);
         
@@ -457,14 +712,24 @@ fn process_distribute_to_all(
             continue;
         }
 
-        let expected_ata = get_associated_token_address(&recipient.wallet, &vesting.mint);
-        let recipient_ata = recipient_atas[i];
+        let expected_ata = get_associated_token_address_with_program_id(
+            &recipient.wallet,
+            &vesting.mint,
+            token_program.key,
+        );
         
         if recipient_ata.key != &expected_ata {
             return Err(VestingError::InvalidRecipientATA.into());
         }
 
-        let ata_account = TokenAccount::unpack(&recipient_ata.data.borrow())?;
+        if let (Some(ata_creation), Some(recipient_wallet)) = (&ata_creation, recipient_wallet) {
+            if recipient_wallet.key != &recipient.wallet {
+                return Err(VestingError::InvalidRecipientWallet.into());
+            }
+            ata_creation.create_if_missing(recipient_wallet, recipient_ata, mint, token_program)?;
+        }
+
+        let ata_account = unpack_token_account(recipient_ata)?;
         if ata_account.owner != recipient.wallet {
             return Err(VestingError::InvalidRecipientATA.into());
         }
@@ -475,72 +740,2539 @@ fn process_distribute_to_all(
         transfer_instructions.push((i, claimable, recipient_ata));
     }
 
-    for (recipient_index, claimable, recipient_ata) in transfer_instructions.iter() {
-        invoke_signed(
-            &transfer(
-                token_program.key,
-                vault_pda.key,
-                recipient_ata.key,
-                &vault_authority_key,
-                &[],
-                *claimable,
-            )?,
-            &[
-                vault_pda.clone(),
-                (*recipient_ata).clone(),
-                vault_authority.clone(),
-                token_program.clone(),
-            ],
-            &[&[b"authority", vesting_pda.key.as_ref(), &[auth_bump]]],
+    for (recipient_index, claimable, recipient_ata) in transfer_instructions {
+        let net_amount = pay_from_vault(
+            vesting_pda,
+            vault_pda,
+            mint,
+            recipient_ata,
+            vault_authority,
+            token_program,
+            auth_bump,
+            claimable,
+            decimals,
         )?;
-        
-        pending_updates.push((*recipient_index, *claimable, current_time));
-        total_distributed += *claimable;
+
+        // Only the paid entries are written back
+        let recipient = &mut recipients[recipient_index];
+        let claimed_amount = u64::from(recipient.claimed_amount)
+            .checked_add(claimable)
+            .ok_or(VestingError::Overflow)?;
+        recipient.claimed_amount = claimed_amount.into();
+        recipient.last_claim_time = current_time.into();
+        total_distributed += claimable;
+
+        VestingEvent::RecipientPaid {
+            vesting: *vesting_pda.key,
+            wallet: recipient.wallet,
+            amount: claimable,
+            net_amount,
+            claimed_amount,
+            timestamp: current_time,
+        }
+        .emit();
     }
 
-    for (recipient_index, claimed_amount, claim_time) in pending_updates {
-        vesting.recipients[recipient_index].claimed_amount += claimed_amount;
-        vesting.recipients[recipient_index].last_claim_time = claim_time;
+    // A call that paid nothing must not restart the cooldown, otherwise anyone
+    // could keep the keeper out with empty subset distributions
+    if total_distributed > 0 {
+        vesting.last_distribution_time = current_time.into();
     }
-    
-    vesting.last_distribution_time = current_time;
-    
-    vesting.pack_into_slice(&mut vesting_pda.data.borrow_mut());
-    
-    if total_distributed == 0 {
-        return Ok(());
+
+    // Only a distribution of a meaningful share earns the reward, and at most
+    // once per interval, so dust-sized calls cannot drain the fee pool
+    if let Some((fee_pool, system_program)) = keeper_accounts {
+        let min_distributed = calculate_recipient_total(
+            vesting.total_amount.into(),
+            MIN_KEEPER_REWARD_BASIS_POINTS,
+        ).max(1);
+        let last_keeper_reward_time = i64::from(vesting.last_keeper_reward_time);
+
+        if total_distributed < min_distributed {
+            msg!("Distributed amount below keeper reward minimum, skipping");
+        } else if last_keeper_reward_time > 0
+            && current_time - last_keeper_reward_time < KEEPER_REWARD_INTERVAL
+        {
+            msg!("Keeper reward interval not elapsed, skipping");
+        } else if pay_keeper_reward(
+            program_id,
+            vesting_pda,
+            fee_pool,
+            caller,
+            system_program,
+            keeper_reward_lamports,
+        )? {
+            vesting.last_keeper_reward_time = current_time.into();
+        }
     }
     
     Ok(())
 }
 
-fn calculate_vested_amount(
-    total_amount: u64,
-    current_time: i64,
-    start_time: i64,
-    schedule: &VestingSchedule,
-) -> u64 {
-    if current_time < start_time {
-        return 0;
+/// Pays the keeper reward from the system-owned `["fee_pool", vesting]` PDA.
+/// The pool is topped up with plain SOL transfers; when it cannot cover the
+/// reward while staying rent-exempt the distribution still succeeds unpaid.
+/// Returns whether the reward was paid.
+fn pay_keeper_reward<'a>(
+    program_id: &Pubkey,
+    vesting_pda: &AccountInfo<'a>,
+    fee_pool: &AccountInfo<'a>,
+    keeper: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    reward: u64,
+) -> Result<bool, ProgramError> {
+    let (fee_pool_address, fee_pool_bump) =
+        Pubkey::find_program_address(&[b"fee_pool", vesting_pda.key.as_ref()], program_id);
+    if fee_pool.key != &fee_pool_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+    if fee_pool.owner != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if system_program.key != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidSystemProgram.into());
     }
 
-    let elapsed = current_time - start_time;
-    
-    let tge_amount = (total_amount as u128 * schedule.tge_basis_points as u128 / BASIS_POINTS_TOTAL as u128) as u64;
-    
-    if elapsed < schedule.cliff_period {
-        return tge_amount;
+    let reserve = Rent::get()?.minimum_balance(0);
+    let required = reward.checked_add(reserve).ok_or(VestingError::Overflow)?;
+    if fee_pool.lamports() < required {
+        msg!("Fee pool cannot cover keeper reward, skipping");
+        return Ok(false);
     }
-    
-    if elapsed >= schedule.vesting_period {
-        return total_amount;
+
+    invoke_signed(
+        &system_instruction::transfer(fee_pool.key, keeper.key, reward),
+        &[
+            fee_pool.clone(),
+            keeper.clone(),
+            system_program.clone(),
+        ],
+        &[&[b"fee_pool", vesting_pda.key.as_ref(), &[fee_pool_bump]]],
+    )?;
+
+    Ok(true)
+}
+
+fn process_claim_own(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    create_missing_ata: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let recipient_wallet = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let vault_pda = next_account_info(account_info_iter)?;
+    let recipient_ata = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let ata_creation = if create_missing_ata {
+        Some(AtaCreationAccounts::next(account_info_iter)?)
+    } else {
+        None
+    };
+
+    if !recipient_wallet.is_signer {
+        return Err(VestingError::NotSigner.into());
     }
-    
-    let vesting_amount = total_amount - tge_amount;
-    let vesting_duration = schedule.vesting_period - schedule.cliff_period;
-    let vesting_elapsed = elapsed - schedule.cliff_period;
-    
-    let linear_vested = (vesting_amount as u128 * vesting_elapsed as u128 / vesting_duration as u128) as u64;
 
-    tge_amount + linear_vested
-}
\ No newline at end of file
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+    if vault_pda.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if clock.key != &solana_program::sysvar::clock::ID {
+        return Err(VestingError::InvalidClockSysvar.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, recipients) = load_vesting_mut(&mut vesting_data)?;
+
+    if !vesting.uses_inline_recipients() {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    if u64::from(vesting.total_amount) == 0 {
+        return Err(VestingError::NotFunded.into());
+    }
+
+    if !bool::from(vesting.is_finalized) {
+        return Err(VestingError::NotFinalized.into());
+    }
+
+    let recipient_index = recipients
+        .iter()
+        .position(|r| r.wallet == *recipient_wallet.key)
+        .ok_or(VestingError::RecipientNotFound)?;
+
+    let (vault_address, _) = 
+        Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
+    if vault_pda.key != &vault_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let (vault_authority_key, auth_bump) = 
+        Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
+    if vault_authority.key != &vault_authority_key {
+        return Err(VestingError::InvalidAuthority.into());
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let vault_account = unpack_token_account(vault_pda)?;
+    if vault_account.owner != vault_authority_key {
+        return Err(VestingError::InvalidTokenOwner.into());
+    }
+    if vault_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let expected_ata = get_associated_token_address_with_program_id(
+        recipient_wallet.key,
+        &vesting.mint,
+        token_program.key,
+    );
+    if recipient_ata.key != &expected_ata {
+        return Err(VestingError::InvalidRecipientATA.into());
+    }
+
+    if let Some(ata_creation) = &ata_creation {
+        ata_creation.validate()?;
+        ata_creation.create_if_missing(recipient_wallet, recipient_ata, mint, token_program)?;
+    }
+
+    let ata_account = unpack_token_account(recipient_ata)?;
+    if ata_account.owner != *recipient_wallet.key {
+        return Err(VestingError::InvalidRecipientATA.into());
+    }
+    if ata_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let clock = Clock::from_account_info(clock)?;
+    let current_time = clock.unix_timestamp;
+
+    if vesting.is_paused(current_time) {
+        return Err(VestingError::VestingPaused.into());
+    }
+
+    let recipient = Recipient::from(&recipients[recipient_index]);
+    let vested_amount = calculate_recipient_vested(vesting, &recipient, current_time)?;
+
+    let claimable = vested_amount.saturating_sub(recipient.claimed_amount);
+    if claimable == 0 {
+        return Err(VestingError::NoClaimableAmount.into());
+    }
+
+    let net_amount = pay_from_vault(
+        vesting_pda,
+        vault_pda,
+        mint,
+        recipient_ata,
+        vault_authority,
+        token_program,
+        auth_bump,
+        claimable,
+        decimals,
+    )?;
+
+    let claimed_amount = recipient.claimed_amount
+        .checked_add(claimable)
+        .ok_or(VestingError::Overflow)?;
+    let entry = &mut recipients[recipient_index];
+    entry.claimed_amount = claimed_amount.into();
+    entry.last_claim_time = current_time.into();
+
+    VestingEvent::RecipientPaid {
+        vesting: *vesting_pda.key,
+        wallet: recipient.wallet,
+        amount: claimable,
+        net_amount,
+        claimed_amount,
+        timestamp: current_time,
+    }
+    .emit();
+
+    Ok(())
+}
+
+/// Terminates one recipient of a revocable vesting. Tokens vested up to now stay
+/// claimable by the recipient; the unvested remainder goes to the clawback account.
+fn process_revoke(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    recipient_index: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let revoker = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let vault_pda = next_account_info(account_info_iter)?;
+    let clawback_token = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+
+    if !revoker.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+    if vault_pda.owner != token_program.key || clawback_token.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if clock.key != &solana_program::sysvar::clock::ID {
+        return Err(VestingError::InvalidClockSysvar.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, recipients) = load_vesting_mut(&mut vesting_data)?;
+
+    if !vesting.uses_inline_recipients() {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    if !bool::from(vesting.revocable) {
+        return Err(VestingError::NotRevocable.into());
+    }
+
+    if vesting.revoker != *revoker.key {
+        return Err(VestingError::UnauthorizedAccess.into());
+    }
+
+    if !bool::from(vesting.is_finalized) {
+        return Err(VestingError::NotFinalized.into());
+    }
+
+    let recipient_index = recipient_index as usize;
+    if recipient_index >= recipients.len() {
+        return Err(VestingError::InvalidRecipientIndex.into());
+    }
+
+    if clawback_token.key != &vesting.clawback_account {
+        return Err(VestingError::InvalidClawbackAccount.into());
+    }
+    if unpack_token_account(clawback_token)?.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let (vault_address, _) = 
+        Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
+    if vault_pda.key != &vault_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let (vault_authority_key, auth_bump) = 
+        Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
+    if vault_authority.key != &vault_authority_key {
+        return Err(VestingError::InvalidAuthority.into());
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let clock = Clock::from_account_info(clock)?;
+    let current_time = clock.unix_timestamp;
+
+    let unvested = revoke_recipient(vesting, &mut recipients[recipient_index], current_time)?;
+
+    if unvested > 0 {
+        pay_from_vault(
+            vesting_pda,
+            vault_pda,
+            mint,
+            clawback_token,
+            vault_authority,
+            token_program,
+            auth_bump,
+            unvested,
+            decimals,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Moves a recipient slot to a new wallet. Only the current wallet can sign;
+/// the allocation, claimed amount and revocation state stay with the slot.
+/// Marks the recipient revoked at `current_time` and returns the unvested
+/// part of its allocation that goes back to the clawback account.
+fn revoke_recipient(
+    vesting: &VestingHeader,
+    entry: &mut PodRecipient,
+    current_time: i64,
+) -> Result<u64, ProgramError> {
+    if i64::from(entry.revoked_at) != 0 {
+        return Err(VestingError::AlreadyRevoked.into());
+    }
+
+    let recipient = Recipient::from(&*entry);
+    let recipient_total = calculate_recipient_total(vesting.total_amount.into(), recipient.basis_points);
+    let vested_amount = calculate_recipient_vested(vesting, &recipient, current_time)?;
+    let unvested = recipient_total
+        .checked_sub(vested_amount)
+        .ok_or(VestingError::Underflow)?;
+
+    entry.revoked_at = current_time.into();
+    Ok(unvested)
+}
+
+fn process_rotate_recipient_wallet(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_wallet: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let current_wallet = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+
+    if !current_wallet.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, recipients) = load_vesting_mut(&mut vesting_data)?;
+
+    if !vesting.uses_inline_recipients() {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    let recipient_index = recipients
+        .iter()
+        .position(|r| r.wallet == *current_wallet.key)
+        .ok_or(VestingError::RecipientNotFound)?;
+
+    if new_wallet == Pubkey::default() {
+        return Err(VestingError::InvalidRecipientWallet.into());
+    }
+    if recipients.iter().any(|r| r.wallet == new_wallet) {
+        return Err(VestingError::DuplicateRecipient.into());
+    }
+
+    recipients[recipient_index].wallet = new_wallet;
+
+    Ok(())
+}
+
+/// Closes a fully distributed vesting. Whatever the vault still holds (rounding
+/// dust, stray deposits) goes to a token account of `rent_recipient`, withheld
+/// Token-2022 fees are harvested to the mint, and then the vault is closed
+/// and the vesting account wiped, all rent going to `rent_recipient` together
+/// with whatever the keeper fee pool still holds. In the recipient-account and
+/// merkle modes the claim totals kept on the header show that nothing is left unpaid.
+fn process_close(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let vault_pda = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let rent_recipient = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let dust_token = next_account_info(account_info_iter)?;
+    let fee_pool = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+    if vault_pda.owner != token_program.key || dust_token.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if system_program.key != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidSystemProgram.into());
+    }
+
+    let vesting_data = vesting_pda.data.borrow();
+    let (vesting, recipients) = load_vesting(&vesting_data)?;
+
+    if !bool::from(vesting.is_finalized) {
+        return Err(VestingError::NotFinalized.into());
+    }
+
+    if rent_recipient.key != &vesting.rent_recipient {
+        return Err(VestingError::InvalidRentRecipient.into());
+    }
+
+    let fully_distributed = if vesting.uses_inline_recipients() {
+        outstanding_inline_amount(vesting, recipients)? == 0
+    } else {
+        external_claims_settled(vesting)
+    };
+    if !fully_distributed {
+        return Err(VestingError::NotFullyDistributed.into());
+    }
+
+    let (vault_address, _) = 
+        Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
+    if vault_pda.key != &vault_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let (vault_authority_key, auth_bump) = 
+        Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
+    if vault_authority.key != &vault_authority_key {
+        return Err(VestingError::InvalidAuthority.into());
+    }
+
+    let (fee_pool_address, fee_pool_bump) =
+        Pubkey::find_program_address(&[b"fee_pool", vesting_pda.key.as_ref()], program_id);
+    if fee_pool.key != &fee_pool_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let dust_account = unpack_token_account(dust_token)?;
+    if dust_account.owner != vesting.rent_recipient {
+        return Err(VestingError::InvalidTokenOwner.into());
+    }
+    if dust_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    // A Token-2022 vault cannot be closed while it holds withheld transfer fees
+    let (vault_balance, withheld_fees) = {
+        let vault_data = vault_pda.data.borrow();
+        let vault_state = StateWithExtensions::<TokenAccount>::unpack(&vault_data)?;
+        let withheld_fees = vault_state
+            .get_extension::<TransferFeeAmount>()
+            .map(|fee| u64::from(fee.withheld_amount))
+            .unwrap_or(0);
+        (vault_state.base.amount, withheld_fees)
+    };
+
+    if withheld_fees > 0 {
+        invoke(
+            &harvest_withheld_tokens_to_mint(token_program.key, mint.key, &[vault_pda.key])?,
+            &[
+                mint.clone(),
+                vault_pda.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+
+    if vault_balance > 0 {
+        pay_from_vault(
+            vesting_pda,
+            vault_pda,
+            mint,
+            dust_token,
+            vault_authority,
+            token_program,
+            auth_bump,
+            vault_balance,
+            decimals,
+        )?;
+    }
+
+    invoke_signed(
+        &close_account(
+            token_program.key,
+            vault_pda.key,
+            rent_recipient.key,
+            &vault_authority_key,
+            &[],
+        )?,
+        &[
+            vault_pda.clone(),
+            rent_recipient.clone(),
+            vault_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&[b"authority", vesting_pda.key.as_ref(), &[auth_bump]]],
+    )?;
+
+    // Without a vesting no keeper can be rewarded from the pool again
+    let fee_pool_lamports = fee_pool.lamports();
+    if fee_pool_lamports > 0 && fee_pool.owner == &solana_program::system_program::ID {
+        invoke_signed(
+            &system_instruction::transfer(fee_pool.key, rent_recipient.key, fee_pool_lamports),
+            &[
+                fee_pool.clone(),
+                rent_recipient.clone(),
+                system_program.clone(),
+            ],
+            &[&[b"fee_pool", vesting_pda.key.as_ref(), &[fee_pool_bump]]],
+        )?;
+    }
+
+    let vesting_lamports = vesting_pda.lamports();
+    **rent_recipient.try_borrow_mut_lamports()? = rent_recipient
+        .lamports()
+        .checked_add(vesting_lamports)
+        .ok_or(VestingError::Overflow)?;
+    **vesting_pda.try_borrow_mut_lamports()? = 0;
+    drop(vesting_data);
+    vesting_pda.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+/// Sends whatever the vault holds above the outstanding recipient obligations
+/// (stray deposits, rounding dust of `recipient_total`) to a token account
+/// chosen by the initializer.
+fn process_sweep_surplus(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let initializer = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let vault_pda = next_account_info(account_info_iter)?;
+    let destination_token = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+
+    if !initializer.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+    if vault_pda.owner != token_program.key || destination_token.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    let vesting_data = vesting_pda.data.borrow();
+    let (vesting, recipients) = load_vesting(&vesting_data)?;
+
+    if !vesting.uses_inline_recipients() {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    if vesting.initializer != *initializer.key {
+        return Err(VestingError::NotInitializer.into());
+    }
+
+    if !bool::from(vesting.is_finalized) {
+        return Err(VestingError::NotFinalized.into());
+    }
+
+    let (vault_address, _) = 
+        Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
+    if vault_pda.key != &vault_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let (vault_authority_key, auth_bump) = 
+        Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
+    if vault_authority.key != &vault_authority_key {
+        return Err(VestingError::InvalidAuthority.into());
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    if unpack_token_account(destination_token)?.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let outstanding = outstanding_inline_amount(vesting, recipients)?;
+
+    let vault_balance = unpack_token_account(vault_pda)?.amount;
+    let surplus = vault_balance.saturating_sub(outstanding);
+    if surplus == 0 {
+        return Err(VestingError::NoTokensToWithdraw.into());
+    }
+
+    pay_from_vault(
+        vesting_pda,
+        vault_pda,
+        mint,
+        destination_token,
+        vault_authority,
+        token_program,
+        auth_bump,
+        surplus,
+        decimals,
+    )?;
+
+    Ok(())
+}
+
+/// Appends recipients to a vesting that is not funded yet, growing the account
+/// with `realloc`; the initializer pays the extra rent.
+fn process_add_recipients(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    recipients: Vec<RecipientData>,
+    schedule_indices: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let initializer = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !initializer.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    if system_program.key != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidSystemProgram.into());
+    }
+
+    // The account is borrowed only for validation: the rent top-up below
+    // transfers into it and realloc needs it unborrowed
+    let new_len = {
+        let vesting_data = vesting_pda.data.borrow();
+        let (vesting, existing) = load_vesting(&vesting_data)?;
+
+        if !vesting.uses_inline_recipients() {
+            return Err(VestingError::UnsupportedVestingMode.into());
+        }
+
+        if vesting.initializer != *initializer.key {
+            return Err(VestingError::UnauthorizedAccess.into());
+        }
+
+        if bool::from(vesting.is_finalized) {
+            return Err(VestingError::VestingFinalized.into());
+        }
+
+        if existing.len() + recipients.len() > MAX_RECIPIENTS {
+            return Err(VestingError::InvalidRecipientCount.into());
+        }
+
+        validate_new_recipients(existing, &recipients)?;
+
+        if schedule_indices.iter().any(|&index| index >= vesting.schedule_count) {
+            return Err(VestingError::InvalidScheduleIndex.into());
+        }
+
+        VestingAccount::account_len(existing.len() + recipients.len())
+    };
+
+    let new_recipients: Vec<Recipient> = recipients.iter().enumerate()
+        .map(|(i, recipient)| Recipient {
+            wallet: recipient.wallet,
+            basis_points: recipient.basis_points,
+            claimed_amount: 0,
+            last_claim_time: 0,
+            revoked_at: 0,
+            schedule_index: schedule_indices.get(i).copied().unwrap_or(0),
+        })
+        .collect();
+
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(vesting_pda.lamports());
+    if rent_due > 0 {
+        invoke(
+            &system_instruction::transfer(initializer.key, vesting_pda.key, rent_due),
+            &[
+                initializer.clone(),
+                vesting_pda.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+    vesting_pda.realloc(new_len, false)?;
+
+    VestingHeader::append_recipients(&mut vesting_pda.data.borrow_mut(), &new_recipients)?;
+
+    Ok(())
+}
+
+/// Registers one beneficiary of a recipient-account vesting in its own
+/// `["recipient", vesting, wallet]` PDA, paid for by the initializer.
+fn process_create_recipient_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wallet: Pubkey,
+    basis_points: u16,
+    schedule_index: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let initializer = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let recipient_pda = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !initializer.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    if system_program.key != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidSystemProgram.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, _) = load_vesting_mut(&mut vesting_data)?;
+
+    if !bool::from(vesting.recipient_accounts) {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    if vesting.initializer != *initializer.key {
+        return Err(VestingError::UnauthorizedAccess.into());
+    }
+
+    if bool::from(vesting.is_finalized) {
+        return Err(VestingError::VestingFinalized.into());
+    }
+
+    if wallet == Pubkey::default() {
+        return Err(VestingError::InvalidRecipientWallet.into());
+    }
+    if basis_points == 0 {
+        return Err(VestingError::ZeroPercentage.into());
+    }
+    if schedule_index >= vesting.schedule_count {
+        return Err(VestingError::InvalidScheduleIndex.into());
+    }
+
+    let allocated_basis_points = u16::from(vesting.allocated_basis_points)
+        .checked_add(basis_points)
+        .filter(|&total| total <= BASIS_POINTS_TOTAL)
+        .ok_or(VestingError::InvalidTotalPercentage)?;
+
+    let (recipient_address, recipient_bump) = Pubkey::find_program_address(
+        &[b"recipient", vesting_pda.key.as_ref(), wallet.as_ref()],
+        program_id,
+    );
+    if recipient_pda.key != &recipient_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    if !recipient_pda.data_is_empty() {
+        return Err(VestingError::DuplicateRecipient.into());
+    }
+
+    let recipient_lamports = Rent::get()?.minimum_balance(RecipientAccount::LEN);
+    invoke_signed(
+        &system_instruction::create_account(
+            initializer.key,
+            recipient_pda.key,
+            recipient_lamports,
+            RecipientAccount::LEN as u64,
+            program_id,
+        ),
+        &[
+            initializer.clone(),
+            recipient_pda.clone(),
+            system_program.clone(),
+        ],
+        &[&[b"recipient", vesting_pda.key.as_ref(), wallet.as_ref(), &[recipient_bump]]],
+    )?;
+
+    let record = RecipientAccount {
+        is_initialized: true,
+        vesting: *vesting_pda.key,
+        recipient: Recipient {
+            wallet,
+            basis_points,
+            schedule_index,
+            ..Recipient::default()
+        },
+    };
+    RecipientAccount::pack(record, &mut recipient_pda.data.borrow_mut())?;
+
+    vesting.allocated_basis_points = allocated_basis_points.into();
+
+    Ok(())
+}
+
+/// Recipient-signed claim in recipient-account mode. The vesting account is
+/// only read, so claims of different recipients do not contend for it.
+fn process_claim_recipient_account(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    create_missing_ata: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let recipient_wallet = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let recipient_pda = next_account_info(account_info_iter)?;
+    let vault_pda = next_account_info(account_info_iter)?;
+    let recipient_ata = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let ata_creation = if create_missing_ata {
+        Some(AtaCreationAccounts::next(account_info_iter)?)
+    } else {
+        None
+    };
+
+    if !recipient_wallet.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id || recipient_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+    if vault_pda.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if clock.key != &solana_program::sysvar::clock::ID {
+        return Err(VestingError::InvalidClockSysvar.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, _) = load_vesting_mut(&mut vesting_data)?;
+
+    if !bool::from(vesting.recipient_accounts) {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    if u64::from(vesting.total_amount) == 0 {
+        return Err(VestingError::NotFunded.into());
+    }
+
+    if !bool::from(vesting.is_finalized) {
+        return Err(VestingError::NotFinalized.into());
+    }
+
+    let (recipient_address, _) = Pubkey::find_program_address(
+        &[b"recipient", vesting_pda.key.as_ref(), recipient_wallet.key.as_ref()],
+        program_id,
+    );
+    if recipient_pda.key != &recipient_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let mut record = RecipientAccount::unpack(&recipient_pda.data.borrow())?;
+    if record.vesting != *vesting_pda.key || record.recipient.wallet != *recipient_wallet.key {
+        return Err(VestingError::RecipientNotFound.into());
+    }
+
+    let (vault_address, _) = 
+        Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
+    if vault_pda.key != &vault_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let (vault_authority_key, auth_bump) = 
+        Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
+    if vault_authority.key != &vault_authority_key {
+        return Err(VestingError::InvalidAuthority.into());
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let vault_account = unpack_token_account(vault_pda)?;
+    if vault_account.owner != vault_authority_key {
+        return Err(VestingError::InvalidTokenOwner.into());
+    }
+    if vault_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let expected_ata = get_associated_token_address_with_program_id(
+        recipient_wallet.key,
+        &vesting.mint,
+        token_program.key,
+    );
+    if recipient_ata.key != &expected_ata {
+        return Err(VestingError::InvalidRecipientATA.into());
+    }
+
+    if let Some(ata_creation) = &ata_creation {
+        ata_creation.validate()?;
+        ata_creation.create_if_missing(recipient_wallet, recipient_ata, mint, token_program)?;
+    }
+
+    let ata_account = unpack_token_account(recipient_ata)?;
+    if ata_account.owner != *recipient_wallet.key {
+        return Err(VestingError::InvalidRecipientATA.into());
+    }
+    if ata_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let current_time = Clock::from_account_info(clock)?.unix_timestamp;
+
+    if vesting.is_paused(current_time) {
+        return Err(VestingError::VestingPaused.into());
+    }
+
+    let vested_amount = calculate_recipient_vested(vesting, &record.recipient, current_time)?;
+    let claimable = vested_amount.saturating_sub(record.recipient.claimed_amount);
+    if claimable == 0 {
+        return Err(VestingError::NoClaimableAmount.into());
+    }
+
+    let net_amount = pay_from_vault(
+        vesting_pda,
+        vault_pda,
+        mint,
+        recipient_ata,
+        vault_authority,
+        token_program,
+        auth_bump,
+        claimable,
+        decimals,
+    )?;
+
+    record.recipient.claimed_amount = record.recipient.claimed_amount
+        .checked_add(claimable)
+        .ok_or(VestingError::Overflow)?;
+    record.recipient.last_claim_time = current_time;
+
+    // A recipient settles once; later claims have nothing left to pay
+    if record.recipient.claimed_amount >= calculate_recipient_entitlement(vesting, &record.recipient)? {
+        let settled_basis_points = u16::from(vesting.settled_basis_points)
+            .checked_add(record.recipient.basis_points)
+            .ok_or(VestingError::Overflow)?;
+        vesting.settled_basis_points = settled_basis_points.into();
+    }
+
+    VestingEvent::RecipientPaid {
+        vesting: *vesting_pda.key,
+        wallet: record.recipient.wallet,
+        amount: claimable,
+        net_amount,
+        claimed_amount: record.recipient.claimed_amount,
+        timestamp: current_time,
+    }
+    .emit();
+
+    RecipientAccount::pack(record, &mut recipient_pda.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Claim of one merkle leaf. The proof is checked against the stored root and
+/// the leaf's progress is kept in a `["receipt", vesting, wallet]` PDA that the
+/// claimant pays for on the first claim.
+fn process_claim_merkle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    allocation: u64,
+    proof: &[[u8; 32]],
+    create_missing_ata: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let recipient_wallet = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let receipt_pda = next_account_info(account_info_iter)?;
+    let vault_pda = next_account_info(account_info_iter)?;
+    let recipient_ata = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let mint = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let ata_creation = if create_missing_ata {
+        Some(AtaCreationAccounts::next(account_info_iter)?)
+    } else {
+        None
+    };
+
+    if !recipient_wallet.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if !is_token_program(token_program.key) {
+        return Err(VestingError::InvalidTokenProgram.into());
+    }
+    if vault_pda.owner != token_program.key {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+    if clock.key != &solana_program::sysvar::clock::ID {
+        return Err(VestingError::InvalidClockSysvar.into());
+    }
+    if system_program.key != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidSystemProgram.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, _) = load_vesting_mut(&mut vesting_data)?;
+
+    if !vesting.is_merkle() {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    if u64::from(vesting.total_amount) == 0 {
+        return Err(VestingError::NotFunded.into());
+    }
+
+    if !bool::from(vesting.is_finalized) {
+        return Err(VestingError::NotFinalized.into());
+    }
+
+    let leaf = merkle_leaf(recipient_wallet.key, allocation);
+    if !verify_merkle_proof(leaf, proof, &vesting.merkle_root) {
+        return Err(VestingError::InvalidMerkleProof.into());
+    }
+
+    let (receipt_address, receipt_bump) = Pubkey::find_program_address(
+        &[b"receipt", vesting_pda.key.as_ref(), recipient_wallet.key.as_ref()],
+        program_id,
+    );
+    if receipt_pda.key != &receipt_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let mut receipt = if receipt_pda.data_is_empty() {
+        let receipt_lamports = Rent::get()?.minimum_balance(ClaimReceipt::LEN);
+        invoke_signed(
+            &system_instruction::create_account(
+                recipient_wallet.key,
+                receipt_pda.key,
+                receipt_lamports,
+                ClaimReceipt::LEN as u64,
+                program_id,
+            ),
+            &[
+                recipient_wallet.clone(),
+                receipt_pda.clone(),
+                system_program.clone(),
+            ],
+            &[&[b"receipt", vesting_pda.key.as_ref(), recipient_wallet.key.as_ref(), &[receipt_bump]]],
+        )?;
+        ClaimReceipt {
+            is_initialized: true,
+            vesting: *vesting_pda.key,
+            wallet: *recipient_wallet.key,
+            allocation,
+            claimed_amount: 0,
+            last_claim_time: 0,
+        }
+    } else {
+        if receipt_pda.owner != program_id {
+            return Err(VestingError::InvalidAccountOwner.into());
+        }
+        ClaimReceipt::unpack(&receipt_pda.data.borrow())?
+    };
+    if receipt.vesting != *vesting_pda.key
+        || receipt.wallet != *recipient_wallet.key
+        || receipt.allocation != allocation
+    {
+        return Err(VestingError::RecipientNotFound.into());
+    }
+
+    let (vault_address, _) = 
+        Pubkey::find_program_address(&[b"vault", vesting_pda.key.as_ref()], program_id);
+    if vault_pda.key != &vault_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let (vault_authority_key, auth_bump) = 
+        Pubkey::find_program_address(&[b"authority", vesting_pda.key.as_ref()], program_id);
+    if vault_authority.key != &vault_authority_key {
+        return Err(VestingError::InvalidAuthority.into());
+    }
+
+    if mint.key != &vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+    let decimals = unpack_mint(mint)?.decimals;
+
+    let vault_account = unpack_token_account(vault_pda)?;
+    if vault_account.owner != vault_authority_key {
+        return Err(VestingError::InvalidTokenOwner.into());
+    }
+    if vault_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let expected_ata = get_associated_token_address_with_program_id(
+        recipient_wallet.key,
+        &vesting.mint,
+        token_program.key,
+    );
+    if recipient_ata.key != &expected_ata {
+        return Err(VestingError::InvalidRecipientATA.into());
+    }
+
+    if let Some(ata_creation) = &ata_creation {
+        ata_creation.validate()?;
+        ata_creation.create_if_missing(recipient_wallet, recipient_ata, mint, token_program)?;
+    }
+
+    let ata_account = unpack_token_account(recipient_ata)?;
+    if ata_account.owner != *recipient_wallet.key {
+        return Err(VestingError::InvalidRecipientATA.into());
+    }
+    if ata_account.mint != vesting.mint {
+        return Err(VestingError::MintMismatch.into());
+    }
+
+    let current_time = Clock::from_account_info(clock)?.unix_timestamp;
+
+    if vesting.is_paused(current_time) {
+        return Err(VestingError::VestingPaused.into());
+    }
+
+    let vested_amount = calculate_vested_amount(
+        allocation,
+        current_time,
+        vesting.start_time.into(),
+        &vesting.schedule(0).ok_or(VestingError::InvalidScheduleIndex)?,
+    );
+    let claimable = vested_amount.saturating_sub(receipt.claimed_amount);
+    if claimable == 0 {
+        return Err(VestingError::NoClaimableAmount.into());
+    }
+
+    let net_amount = pay_from_vault(
+        vesting_pda,
+        vault_pda,
+        mint,
+        recipient_ata,
+        vault_authority,
+        token_program,
+        auth_bump,
+        claimable,
+        decimals,
+    )?;
+
+    receipt.claimed_amount = receipt.claimed_amount
+        .checked_add(claimable)
+        .ok_or(VestingError::Overflow)?;
+    receipt.last_claim_time = current_time;
+    let merkle_claimed_amount = u64::from(vesting.merkle_claimed_amount)
+        .checked_add(claimable)
+        .ok_or(VestingError::Overflow)?;
+    vesting.merkle_claimed_amount = merkle_claimed_amount.into();
+
+    VestingEvent::RecipientPaid {
+        vesting: *vesting_pda.key,
+        wallet: receipt.wallet,
+        amount: claimable,
+        net_amount,
+        claimed_amount: receipt.claimed_amount,
+        timestamp: current_time,
+    }
+    .emit();
+
+    ClaimReceipt::pack(receipt, &mut receipt_pda.data.borrow_mut())?;
+
+    Ok(())
+}
+
+/// Rewrites a v1 vesting account in the current layout, keeping every field
+/// and recipient balance. Any signer may pay the rent difference.
+fn process_migrate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let payer = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    if system_program.key != &solana_program::system_program::ID {
+        return Err(VestingError::InvalidSystemProgram.into());
+    }
+
+    let mut vesting = VestingAccount::unpack_from_slice(&vesting_pda.data.borrow())?;
+
+    if !vesting.is_initialized {
+        return Err(VestingError::NotInitialized.into());
+    }
+
+    if vesting.version == CURRENT_VERSION {
+        return Err(VestingError::AlreadyMigrated.into());
+    }
+
+    let new_len = VestingAccount::account_len(vesting.recipients.len());
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(vesting_pda.lamports());
+    if rent_due > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, vesting_pda.key, rent_due),
+            &[
+                payer.clone(),
+                vesting_pda.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+    vesting_pda.realloc(new_len, false)?;
+
+    msg!("Migrated vesting from version {} to {}", vesting.version, CURRENT_VERSION);
+    vesting.version = CURRENT_VERSION;
+    vesting.pack_into_slice(&mut vesting_pda.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Read-only view of vested, claimed and claimable amounts, returned through
+/// return data so wallets can simulate it instead of re-implementing the
+/// schedule math. Inline recipients are read from the vesting; in the other
+/// modes the recipient accounts or claim receipts follow the vesting account.
+fn process_query_claimable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    first_index: u8,
+    count: u8,
+    timestamp: Option<i64>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_pda = next_account_info(account_info_iter)?;
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    // Nothing is written here, so a v1 account is read through an in-memory
+    // copy in the current layout instead of requiring Migrate first
+    let vesting_data = vesting_pda.data.borrow();
+    let migrated;
+    let data: &[u8] = match vesting_data.first() {
+        Some(&CURRENT_VERSION) | Some(0) | None => &vesting_data,
+        Some(_) => {
+            let baseline = VestingAccount::unpack_from_slice(&vesting_data)?;
+            let mut current = vec![0; VestingAccount::account_len(baseline.recipients.len())];
+            baseline.pack_into_slice(&mut current);
+            migrated = current;
+            &migrated
+        }
+    };
+    let (vesting, inline_recipients) = load_vesting(data)?;
+
+    let current_time = match timestamp {
+        Some(timestamp) => timestamp,
+        None => Clock::get()?.unix_timestamp,
+    };
+
+    let amounts = |vested: u64, claimed: u64| ClaimableAmounts {
+        vested,
+        claimed,
+        claimable: vested.saturating_sub(claimed),
+    };
+
+    let mut recipients = Vec::new();
+    if vesting.uses_inline_recipients() {
+        for r in inline_recipients.iter().map(Recipient::from) {
+            recipients.push(amounts(calculate_recipient_vested(vesting, &r, current_time)?, r.claimed_amount));
+        }
+    } else {
+        for account in account_info_iter {
+            if account.owner != program_id {
+                return Err(VestingError::InvalidAccountOwner.into());
+            }
+            if vesting.is_merkle() {
+                let receipt = ClaimReceipt::unpack(&account.data.borrow())?;
+                if receipt.vesting != *vesting_pda.key {
+                    return Err(VestingError::RecipientNotFound.into());
+                }
+                let vested = calculate_vested_amount(
+                    receipt.allocation,
+                    current_time,
+                    vesting.start_time.into(),
+                    &vesting.schedule(0).ok_or(VestingError::InvalidScheduleIndex)?,
+                );
+                recipients.push(amounts(vested, receipt.claimed_amount));
+            } else {
+                let record = RecipientAccount::unpack(&account.data.borrow())?;
+                if record.vesting != *vesting_pda.key {
+                    return Err(VestingError::RecipientNotFound.into());
+                }
+                let vested = calculate_recipient_vested(vesting, &record.recipient, current_time)?;
+                recipients.push(amounts(vested, record.recipient.claimed_amount));
+            }
+        }
+    }
+
+    if recipients.len() > MAX_RECIPIENTS {
+        return Err(VestingError::InvalidRecipientCount.into());
+    }
+    let first_index = first_index as usize;
+    if first_index > recipients.len() {
+        return Err(VestingError::InvalidRecipientIndex.into());
+    }
+    let count = match count as usize {
+        0 => (recipients.len() - first_index).min(MAX_QUERY_ENTRIES),
+        count if count > MAX_QUERY_ENTRIES => {
+            return Err(VestingError::InvalidRecipientCount.into());
+        }
+        count if first_index + count > recipients.len() => {
+            return Err(VestingError::InvalidRecipientIndex.into());
+        }
+        count => count,
+    };
+
+    let query = ClaimableQuery {
+        timestamp: current_time,
+        recipient_count: recipients.len() as u8,
+        entries: recipients[first_index..first_index + count].to_vec(),
+    };
+    set_return_data(&query.pack());
+
+    Ok(())
+}
+
+/// `QueryClaimable` for a merkle leaf before or after its first claim. Without
+/// a receipt the leaf must be proven against the root and nothing is claimed
+/// yet; with one, the allocation must match the receipt.
+fn process_query_merkle_claimable(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wallet: &Pubkey,
+    allocation: u64,
+    proof: &[[u8; 32]],
+    timestamp: Option<i64>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let receipt_pda = next_account_info(account_info_iter)?;
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    let vesting_data = vesting_pda.data.borrow();
+    let (vesting, _) = load_vesting(&vesting_data)?;
+
+    if !vesting.is_merkle() {
+        return Err(VestingError::UnsupportedVestingMode.into());
+    }
+
+    let (receipt_address, _) = Pubkey::find_program_address(
+        &[b"receipt", vesting_pda.key.as_ref(), wallet.as_ref()],
+        program_id,
+    );
+    if receipt_pda.key != &receipt_address {
+        return Err(VestingError::InvalidPDA.into());
+    }
+
+    let claimed_amount = if receipt_pda.data_is_empty() {
+        if !verify_merkle_proof(merkle_leaf(wallet, allocation), proof, &vesting.merkle_root) {
+            return Err(VestingError::InvalidMerkleProof.into());
+        }
+        0
+    } else {
+        if receipt_pda.owner != program_id {
+            return Err(VestingError::InvalidAccountOwner.into());
+        }
+        let receipt = ClaimReceipt::unpack(&receipt_pda.data.borrow())?;
+        if receipt.vesting != *vesting_pda.key
+            || receipt.wallet != *wallet
+            || receipt.allocation != allocation
+        {
+            return Err(VestingError::RecipientNotFound.into());
+        }
+        receipt.claimed_amount
+    };
+
+    let current_time = match timestamp {
+        Some(timestamp) => timestamp,
+        None => Clock::get()?.unix_timestamp,
+    };
+    let vested = calculate_vested_amount(
+        allocation,
+        current_time,
+        vesting.start_time.into(),
+        &vesting.schedule(0).ok_or(VestingError::InvalidScheduleIndex)?,
+    );
+
+    let query = ClaimableQuery {
+        timestamp: current_time,
+        recipient_count: 1,
+        entries: vec![ClaimableAmounts {
+            vested,
+            claimed: claimed_amount,
+            claimable: vested.saturating_sub(claimed_amount),
+        }],
+    };
+    set_return_data(&query.pack());
+
+    Ok(())
+}
+
+/// Pause or unpause by the guardian. A pause lasts at most
+/// `MAX_PAUSE_DURATION` and expires on its own; the time paused is charged to
+/// a `MAX_TOTAL_PAUSE_DURATION` budget (unused time is refunded on Unpause),
+/// so recipients can never be frozen out for good.
+fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let guardian = next_account_info(account_info_iter)?;
+    let vesting_pda = next_account_info(account_info_iter)?;
+    let clock = next_account_info(account_info_iter)?;
+
+    if !guardian.is_signer {
+        return Err(VestingError::NotSigner.into());
+    }
+
+    if vesting_pda.owner != program_id {
+        return Err(VestingError::InvalidAccountOwner.into());
+    }
+
+    let mut vesting_data = vesting_pda.data.borrow_mut();
+    let (vesting, _) = load_vesting_mut(&mut vesting_data)?;
+
+    if vesting.guardian == Pubkey::default() || guardian.key != &vesting.guardian {
+        return Err(VestingError::UnauthorizedAccess.into());
+    }
+
+    if clock.key != &solana_program::sysvar::clock::ID {
+        return Err(VestingError::InvalidClockSysvar.into());
+    }
+    let current_time = Clock::from_account_info(clock)?.unix_timestamp;
+
+    if paused {
+        pause_vesting(vesting, current_time)
+    } else {
+        unpause_vesting(vesting, current_time)
+    }
+}
+
+/// Pauses for `MAX_PAUSE_DURATION`, or for what is left of the total budget.
+fn pause_vesting(vesting: &mut VestingHeader, current_time: i64) -> ProgramResult {
+    if vesting.is_paused(current_time) {
+        return Err(VestingError::VestingPaused.into());
+    }
+    let pause_duration_used = i64::from(vesting.pause_duration_used);
+    let remaining_budget = MAX_TOTAL_PAUSE_DURATION - pause_duration_used;
+    if remaining_budget <= 0 {
+        return Err(VestingError::PauseBudgetExhausted.into());
+    }
+    let duration = remaining_budget.min(MAX_PAUSE_DURATION);
+    let paused_until = current_time
+        .checked_add(duration)
+        .ok_or(VestingError::Overflow)?;
+    vesting.paused_until = paused_until.into();
+    vesting.pause_duration_used = (pause_duration_used + duration).into();
+    Ok(())
+}
+
+/// Ends the pause early and returns its unused part to the budget.
+fn unpause_vesting(vesting: &mut VestingHeader, current_time: i64) -> ProgramResult {
+    if !vesting.is_paused(current_time) {
+        return Err(VestingError::NotPaused.into());
+    }
+    let unused = i64::from(vesting.paused_until) - current_time;
+    vesting.pause_duration_used = (i64::from(vesting.pause_duration_used) - unused).into();
+    vesting.paused_until = 0.into();
+    Ok(())
+}
+
+/// Transfers `gross_amount` from the vault and returns what the recipient ATA
+/// actually received, which is less for mints with a Token-2022 transfer fee.
+#[allow(clippy::too_many_arguments)]
+fn pay_from_vault<'a>(
+    vesting_pda: &AccountInfo<'a>,
+    vault_pda: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    recipient_ata: &AccountInfo<'a>,
+    vault_authority: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    auth_bump: u8,
+    gross_amount: u64,
+    decimals: u8,
+) -> Result<u64, ProgramError> {
+    let balance_before = unpack_token_account(recipient_ata)?.amount;
+
+    invoke_signed(
+        &transfer_checked(
+            token_program.key,
+            vault_pda.key,
+            mint.key,
+            recipient_ata.key,
+            vault_authority.key,
+            &[],
+            gross_amount,
+            decimals,
+        )?,
+        &[
+            vault_pda.clone(),
+            mint.clone(),
+            recipient_ata.clone(),
+            vault_authority.clone(),
+            token_program.clone(),
+        ],
+        &[&[b"authority", vesting_pda.key.as_ref(), &[auth_bump]]],
+    )?;
+
+    let balance_after = unpack_token_account(recipient_ata)?.amount;
+    let net_amount = balance_after
+        .checked_sub(balance_before)
+        .ok_or(VestingError::Underflow)?;
+
+    Ok(net_amount)
+}
+
+/// Accounts needed to create recipient ATAs on the fly:
+/// payer (signer, writable), system program, associated token program.
+struct AtaCreationAccounts<'a, 'b> {
+    payer: &'b AccountInfo<'a>,
+    system_program: &'b AccountInfo<'a>,
+    associated_token_program: &'b AccountInfo<'a>,
+}
+
+impl<'a, 'b> AtaCreationAccounts<'a, 'b> {
+    fn next<I: Iterator<Item = &'b AccountInfo<'a>>>(iter: &mut I) -> Result<Self, ProgramError> {
+        Ok(Self {
+            payer: next_account_info(iter)?,
+            system_program: next_account_info(iter)?,
+            associated_token_program: next_account_info(iter)?,
+        })
+    }
+
+    fn validate(&self) -> ProgramResult {
+        if !self.payer.is_signer {
+            return Err(VestingError::NotSigner.into());
+        }
+        if self.system_program.key != &solana_program::system_program::ID {
+            return Err(VestingError::InvalidSystemProgram.into());
+        }
+        if self.associated_token_program.key != &spl_associated_token_account::ID {
+            return Err(VestingError::InvalidAssociatedTokenProgram.into());
+        }
+        Ok(())
+    }
+
+    fn create_if_missing(
+        &self,
+        wallet: &AccountInfo<'a>,
+        ata: &AccountInfo<'a>,
+        mint: &AccountInfo<'a>,
+        token_program: &AccountInfo<'a>,
+    ) -> ProgramResult {
+        if !ata.data_is_empty() {
+            return Ok(());
+        }
+
+        invoke(
+            &create_associated_token_account_idempotent(
+                self.payer.key,
+                wallet.key,
+                mint.key,
+                token_program.key,
+            ),
+            &[
+                self.payer.clone(),
+                ata.clone(),
+                wallet.clone(),
+                mint.clone(),
+                self.system_program.clone(),
+                token_program.clone(),
+                self.associated_token_program.clone(),
+            ],
+        )
+    }
+}
+
+/// Borrows the header and recipients of an initialized current-layout vesting
+/// straight from the account data. Accounts still in the v1 layout must be
+/// migrated first.
+fn load_vesting(data: &[u8]) -> Result<(&VestingHeader, &[PodRecipient]), ProgramError> {
+    check_vesting_version(data)?;
+    let (vesting, recipients) = VestingHeader::load(data)?;
+    if !vesting.is_initialized() {
+        return Err(VestingError::NotInitialized.into());
+    }
+    Ok((vesting, recipients))
+}
+
+/// Mutable `load_vesting`: handlers write only the fields and recipient
+/// entries they change.
+fn load_vesting_mut(data: &mut [u8]) -> Result<(&mut VestingHeader, &mut [PodRecipient]), ProgramError> {
+    check_vesting_version(data)?;
+    let (vesting, recipients) = VestingHeader::load_mut(data)?;
+    if !vesting.is_initialized() {
+        return Err(VestingError::NotInitialized.into());
+    }
+    Ok((vesting, recipients))
+}
+
+fn check_vesting_version(data: &[u8]) -> ProgramResult {
+    match data.first() {
+        None | Some(0) => Err(VestingError::NotInitialized.into()),
+        Some(&CURRENT_VERSION) => Ok(()),
+        Some(_) => Err(VestingError::MigrationRequired.into()),
+    }
+}
+
+fn is_token_program(key: &Pubkey) -> bool {
+    key == &spl_token::ID || key == &spl_token_2022::ID
+}
+
+/// Rejects Token-2022 mints whose extensions would let a third party block
+/// or seize vault transfers, or make vested tokens unusable for recipients.
+fn validate_mint_extensions<S: BaseStateWithExtensions<Mint>>(
+    mint_state: &S,
+    extensions: &[ExtensionType],
+) -> ProgramResult {
+    for extension in extensions {
+        match extension {
+            ExtensionType::TransferHook
+            | ExtensionType::PermanentDelegate
+            | ExtensionType::NonTransferable => {
+                return Err(VestingError::UnsupportedMintExtension.into());
+            }
+            ExtensionType::DefaultAccountState => {
+                let default_state = mint_state.get_extension::<DefaultAccountState>()?;
+                if default_state.state == AccountState::Frozen as u8 {
+                    return Err(VestingError::UnsupportedMintExtension.into());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Unpacks a legacy or Token-2022 token account, ignoring its extensions.
+fn unpack_token_account(account: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    let data = account.data.borrow();
+    Ok(StateWithExtensions::<TokenAccount>::unpack(&data)?.base)
+}
+
+fn unpack_mint(account: &AccountInfo) -> Result<Mint, ProgramError> {
+    let data = account.data.borrow();
+    Ok(StateWithExtensions::<Mint>::unpack(&data)?.base)
+}
+
+/// Leaf of the merkle tree: `hash(0x00 || wallet || allocation_le)`.
+fn merkle_leaf(wallet: &Pubkey, allocation: u64) -> [u8; 32] {
+    hashv(&[&[0], wallet.as_ref(), &allocation.to_le_bytes()]).to_bytes()
+}
+
+/// Inner nodes are `hash(0x01 || min(a, b) || max(a, b))`, so proofs carry no
+/// left/right flags and a leaf can never be passed off as an inner node.
+fn verify_merkle_proof(leaf: [u8; 32], proof: &[[u8; 32]], root: &[u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        let (first, second) = if node <= *sibling { (node, *sibling) } else { (*sibling, node) };
+        hashv(&[&[1], &first, &second]).to_bytes()
+    });
+    computed == *root
+}
+
+fn calculate_recipient_total(total_amount: u64, basis_points: u16) -> u64 {
+    (total_amount as u128 * basis_points as u128 / BASIS_POINTS_TOTAL as u128) as u64
+}
+
+/// Total a recipient will ever receive: the full share, or what had vested
+/// at revocation time for a revoked recipient.
+fn calculate_recipient_entitlement(
+    vesting: &VestingHeader,
+    recipient: &Recipient,
+) -> Result<u64, ProgramError> {
+    if recipient.revoked_at != 0 {
+        calculate_recipient_vested(vesting, recipient, recipient.revoked_at)
+    } else {
+        Ok(calculate_recipient_total(vesting.total_amount.into(), recipient.basis_points))
+    }
+}
+
+/// Vested amount of one recipient; a revoked recipient stops vesting at `revoked_at`.
+fn calculate_recipient_vested(
+    vesting: &VestingHeader,
+    recipient: &Recipient,
+    current_time: i64,
+) -> Result<u64, ProgramError> {
+    let vesting_time = if recipient.revoked_at != 0 {
+        current_time.min(recipient.revoked_at)
+    } else {
+        current_time
+    };
+    let schedule = vesting
+        .schedule(recipient.schedule_index)
+        .ok_or(VestingError::InvalidScheduleIndex)?;
+
+    Ok(calculate_vested_amount(
+        calculate_recipient_total(vesting.total_amount.into(), recipient.basis_points),
+        vesting_time,
+        vesting.start_time.into(),
+        &schedule,
+    ))
+}
+
+/// Tokens the vault still owes inline recipients: every entitlement minus
+/// what was already claimed. Rounding of `recipient_total` is not owed to anyone.
+fn outstanding_inline_amount(
+    vesting: &VestingHeader,
+    recipients: &[PodRecipient],
+) -> Result<u64, ProgramError> {
+    let mut outstanding = 0u64;
+    for recipient in recipients.iter().map(Recipient::from) {
+        let remaining = calculate_recipient_entitlement(vesting, &recipient)?
+            .saturating_sub(recipient.claimed_amount);
+        outstanding = outstanding.checked_add(remaining).ok_or(VestingError::Overflow)?;
+    }
+    Ok(outstanding)
+}
+
+/// Whether every recipient account or merkle leaf has claimed in full, from
+/// the totals the claim handlers keep on the header. A leaf that was never
+/// claimed adds nothing to `merkle_claimed_amount`.
+fn external_claims_settled(vesting: &VestingHeader) -> bool {
+    if vesting.is_merkle() {
+        u64::from(vesting.merkle_claimed_amount) == u64::from(vesting.merkle_total_allocation)
+    } else {
+        u16::from(vesting.settled_basis_points) == u16::from(vesting.allocated_basis_points)
+    }
+}
+
+/// Checks recipients about to join `existing`: real wallets, no duplicates
+/// across both sets, non-zero shares and at most 100% in total.
+fn validate_new_recipients(existing: &[PodRecipient], recipients: &[RecipientData]) -> ProgramResult {
+    let mut seen_wallets: HashSet<Pubkey> = existing.iter().map(|r| r.wallet).collect();
+    for recipient in recipients {
+        if recipient.wallet == Pubkey::default() {
+            return Err(VestingError::InvalidRecipientWallet.into());
+        }
+        if !seen_wallets.insert(recipient.wallet) {
+            return Err(VestingError::DuplicateRecipient.into());
+        }
+        if recipient.basis_points == 0 {
+            return Err(VestingError::ZeroPercentage.into());
+        }
+    }
+
+    let total_basis_points: u32 = existing.iter()
+        .map(|r| u16::from(r.basis_points) as u32)
+        .chain(recipients.iter().map(|r| r.basis_points as u32))
+        .sum();
+    if total_basis_points > BASIS_POINTS_TOTAL as u32 {
+        return Err(VestingError::InvalidTotalPercentage.into());
+    }
+
+    Ok(())
+}
+
+/// A scheduled start must lie in the future, at most `MAX_START_DELAY` ahead.
+fn validate_start_time(start_time: i64, now: i64) -> ProgramResult {
+    if start_time <= now || start_time - now > MAX_START_DELAY {
+        return Err(VestingError::InvalidStartTime.into());
+    }
+    Ok(())
+}
+
+fn validate_schedule(schedule: &VestingSchedule) -> ProgramResult {
+    if schedule.step_period < 0 {
+        return Err(VestingError::InvalidStepPeriod.into());
+    }
+
+    if schedule.is_piecewise() {
+        if schedule.step_period != 0 {
+            return Err(VestingError::InvalidStepPeriod.into());
+        }
+        return validate_unlock_points(schedule);
+    }
+
+    if schedule.vesting_period > MAX_VESTING_DURATION {
+        return Err(VestingError::VestingDurationTooLong.into());
+    }
+
+    if schedule.cliff_period > MAX_CLIFF_DURATION {
+        return Err(VestingError::CliffDurationTooLong.into());
+    }
+
+    if schedule.cliff_period >= schedule.vesting_period {
+        return Err(VestingError::CliffExceedsVesting.into());
+    }
+
+    if schedule.tge_basis_points > BASIS_POINTS_TOTAL {
+        return Err(VestingError::InvalidPercentage.into());
+    }
+
+    if schedule.step_period > schedule.vesting_period - schedule.cliff_period {
+        return Err(VestingError::InvalidStepPeriod.into());
+    }
+
+    Ok(())
+}
+
+/// Unlock points must be strictly increasing in time, never lower the
+/// cumulative basis points and end at 100%. The linear fields stay unused.
+fn validate_unlock_points(schedule: &VestingSchedule) -> ProgramResult {
+    if schedule.unlock_point_count as usize > MAX_UNLOCK_POINTS {
+        return Err(VestingError::InvalidUnlockSchedule.into());
+    }
+
+    if schedule.cliff_period != 0 || schedule.vesting_period != 0 || schedule.tge_basis_points != 0 {
+        return Err(VestingError::InvalidUnlockSchedule.into());
+    }
+
+    let points = schedule.active_unlock_points();
+    let mut previous: Option<&UnlockPoint> = None;
+    for point in points {
+        if point.offset < 0 || point.cumulative_basis_points > BASIS_POINTS_TOTAL {
+            return Err(VestingError::InvalidUnlockSchedule.into());
+        }
+        if let Some(previous) = previous {
+            if point.offset <= previous.offset
+                || point.cumulative_basis_points < previous.cumulative_basis_points
+            {
+                return Err(VestingError::InvalidUnlockSchedule.into());
+            }
+        }
+        previous = Some(point);
+    }
+
+    match points.last() {
+        Some(last) if last.cumulative_basis_points == BASIS_POINTS_TOTAL => {
+            if last.offset > MAX_VESTING_DURATION {
+                return Err(VestingError::VestingDurationTooLong.into());
+            }
+            Ok(())
+        }
+        _ => Err(VestingError::InvalidUnlockSchedule.into()),
+    }
+}
+
+fn calculate_vested_amount(
+    total_amount: u64,
+    current_time: i64,
+    start_time: i64,
+    schedule: &VestingSchedule,
+) -> u64 {
+    if current_time < start_time {
+        return 0;
+    }
+
+    let elapsed = current_time - start_time;
+
+    if schedule.is_piecewise() {
+        return calculate_piecewise_vested_amount(total_amount, elapsed, schedule.active_unlock_points());
+    }
+    
+    let tge_amount = (total_amount as u128 * schedule.tge_basis_points as u128 / BASIS_POINTS_TOTAL as u128) as u64;
+    
+    if elapsed < schedule.cliff_period {
+        return tge_amount;
+    }
+    
+    if elapsed >= schedule.vesting_period {
+        return total_amount;
+    }
+    
+    let vesting_amount = total_amount - tge_amount;
+    let vesting_duration = schedule.vesting_period - schedule.cliff_period;
+    let mut vesting_elapsed = elapsed - schedule.cliff_period;
+
+    // Step mode: only whole periods count; the final step is covered by the
+    // `elapsed >= vesting_period` branch above and releases any remainder
+    if schedule.step_period > 0 {
+        vesting_elapsed -= vesting_elapsed % schedule.step_period;
+    }
+    
+    let linear_vested = (vesting_amount as u128 * vesting_elapsed as u128 / vesting_duration as u128) as u64;
+
+    tge_amount + linear_vested
+}
+
+/// Nothing is vested before the first point; between two points the amount
+/// grows linearly, and from the last point on everything is vested.
+fn calculate_piecewise_vested_amount(
+    total_amount: u64,
+    elapsed: i64,
+    points: &[UnlockPoint],
+) -> u64 {
+    let amount_at = |point: &UnlockPoint| {
+        (total_amount as u128 * point.cumulative_basis_points as u128 / BASIS_POINTS_TOTAL as u128) as u64
+    };
+
+    let next_index = match points.iter().position(|p| elapsed < p.offset) {
+        Some(0) => return 0,
+        Some(index) => index,
+        None => return total_amount,
+    };
+
+    let from = &points[next_index - 1];
+    let to = &points[next_index];
+    let from_amount = amount_at(from);
+    let to_amount = amount_at(to);
+
+    let segment_elapsed = (elapsed - from.offset) as u128;
+    let segment_duration = (to.offset - from.offset) as u128;
+    let interpolated = (to_amount - from_amount) as u128 * segment_elapsed / segment_duration;
+
+    from_amount + interpolated as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+    use crate::state::PodVestingSchedule;
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut};
+
+    fn inline_recipients(basis_points: &[u16]) -> Vec<PodRecipient> {
+        basis_points
+            .iter()
+            .map(|&basis_points| PodRecipient::from(&Recipient {
+                wallet: Pubkey::new_unique(),
+                basis_points,
+                ..Recipient::default()
+            }))
+            .collect()
+    }
+
+    /// Account data of a current-layout vesting with `recipients` inline.
+    fn vesting_data(vesting: &VestingHeader, recipients: &[PodRecipient]) -> Vec<u8> {
+        let mut vesting = *vesting;
+        vesting.version = CURRENT_VERSION;
+        vesting.is_initialized = true.into();
+        vesting.recipient_count = recipients.len() as u8;
+        let mut data = bytemuck::bytes_of(&vesting).to_vec();
+        data.extend_from_slice(bytemuck::cast_slice(recipients));
+        data
+    }
+
+    fn account<'a>(
+        key: &'a Pubkey,
+        is_signer: bool,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+        owner: &'a Pubkey,
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, is_signer, true, lamports, data, owner, false, 0)
+    }
+
+    fn piecewise_schedule(points: &[(i64, u16)]) -> VestingSchedule {
+        let mut schedule = VestingSchedule {
+            unlock_point_count: points.len() as u8,
+            ..VestingSchedule::default()
+        };
+        for (slot, &(offset, cumulative_basis_points)) in schedule.unlock_points.iter_mut().zip(points) {
+            *slot = UnlockPoint { offset, cumulative_basis_points };
+        }
+        schedule
+    }
+
+    #[test]
+    fn test_piecewise_vested_amount() {
+        let schedule = piecewise_schedule(&[(100, 1000), (200, 5000), (400, 10000)]);
+        let points = schedule.active_unlock_points();
+        let vested = |elapsed| calculate_piecewise_vested_amount(10_000, elapsed, points);
+
+        // Nothing before the first point, then exactly its share on it
+        assert_eq!(vested(0), 0);
+        assert_eq!(vested(99), 0);
+        assert_eq!(vested(100), 1000);
+        // Linear between points
+        assert_eq!(vested(150), 3000);
+        assert_eq!(vested(199), 4960);
+        assert_eq!(vested(200), 5000);
+        assert_eq!(vested(300), 7500);
+        assert_eq!(vested(399), 9975);
+        // Everything from the last point on
+        assert_eq!(vested(400), 10_000);
+        assert_eq!(vested(10_000), 10_000);
+    }
+
+    #[test]
+    fn test_piecewise_vested_amount_rounds_down() {
+        let schedule = piecewise_schedule(&[(0, 3333), (3, 10000)]);
+        let points = schedule.active_unlock_points();
+
+        // A point at offset 0 unlocks its share at start time
+        assert_eq!(calculate_piecewise_vested_amount(10, 0, points), 3);
+        assert_eq!(calculate_piecewise_vested_amount(10, 1, points), 5);
+        assert_eq!(calculate_piecewise_vested_amount(10, 2, points), 7);
+        assert_eq!(calculate_piecewise_vested_amount(10, 3, points), 10);
+    }
+
+    #[test]
+    fn test_piecewise_schedule_relative_to_start_time() {
+        let schedule = piecewise_schedule(&[(100, 2500), (200, 10000)]);
+
+        assert_eq!(calculate_vested_amount(1000, 999, 1000, &schedule), 0);
+        assert_eq!(calculate_vested_amount(1000, 1099, 1000, &schedule), 0);
+        assert_eq!(calculate_vested_amount(1000, 1100, 1000, &schedule), 250);
+        assert_eq!(calculate_vested_amount(1000, 1150, 1000, &schedule), 625);
+        assert_eq!(calculate_vested_amount(1000, 1200, 1000, &schedule), 1000);
+    }
+
+    #[test]
+    fn test_step_vested_amount() {
+        let schedule = VestingSchedule {
+            cliff_period: 100,
+            vesting_period: 1000,
+            tge_basis_points: 1000,
+            step_period: 300,
+            ..VestingSchedule::default()
+        };
+        let vested = |elapsed: i64| calculate_vested_amount(10_000, 5000 + elapsed, 5000, &schedule);
+
+        assert_eq!(calculate_vested_amount(10_000, 4999, 5000, &schedule), 0);
+        // Only the TGE share until the cliff and through the first step
+        assert_eq!(vested(0), 1000);
+        assert_eq!(vested(99), 1000);
+        assert_eq!(vested(100), 1000);
+        assert_eq!(vested(399), 1000);
+        // Each whole step releases its share at once
+        assert_eq!(vested(400), 4000);
+        assert_eq!(vested(699), 4000);
+        assert_eq!(vested(700), 7000);
+        assert_eq!(vested(999), 7000);
+        assert_eq!(vested(1000), 10_000);
+    }
+
+    #[test]
+    fn test_step_vested_amount_releases_remainder() {
+        // 900 seconds after the cliff do not divide into 400-second steps
+        let schedule = VestingSchedule {
+            cliff_period: 100,
+            vesting_period: 1000,
+            step_period: 400,
+            ..VestingSchedule::default()
+        };
+
+        assert_eq!(calculate_vested_amount(9000, 99, 0, &schedule), 0);
+        assert_eq!(calculate_vested_amount(9000, 499, 0, &schedule), 0);
+        assert_eq!(calculate_vested_amount(9000, 500, 0, &schedule), 4000);
+        assert_eq!(calculate_vested_amount(9000, 900, 0, &schedule), 8000);
+        assert_eq!(calculate_vested_amount(9000, 999, 0, &schedule), 8000);
+        // The shorter final step reaches the full allocation
+        assert_eq!(calculate_vested_amount(9000, 1000, 0, &schedule), 9000);
+        assert_eq!(calculate_vested_amount(9000, 5000, 0, &schedule), 9000);
+    }
+
+    #[test]
+    fn test_validate_unlock_points() {
+        let invalid: Result<(), ProgramError> = Err(VestingError::InvalidUnlockSchedule.into());
+
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(0, 1000), (100, 10000)])), Ok(()));
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(100, 10000)])), Ok(()));
+        // Equal cumulative basis points are a plateau and allowed
+        assert_eq!(
+            validate_unlock_points(&piecewise_schedule(&[(100, 5000), (200, 5000), (300, 10000)])),
+            Ok(())
+        );
+
+        // Offsets must be strictly increasing
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(100, 1000), (100, 10000)])), invalid);
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(200, 1000), (100, 10000)])), invalid);
+        // Basis points must not go down
+        assert_eq!(
+            validate_unlock_points(&piecewise_schedule(&[(100, 6000), (200, 5000), (300, 10000)])),
+            invalid
+        );
+        // The last point must reach 100%
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(100, 1000), (200, 9999)])), invalid);
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[])), invalid);
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(100, 10001)])), invalid);
+        assert_eq!(validate_unlock_points(&piecewise_schedule(&[(-1, 10000)])), invalid);
+
+        // The linear fields must stay unused
+        let mut schedule = piecewise_schedule(&[(100, 10000)]);
+        schedule.cliff_period = 10;
+        assert_eq!(validate_unlock_points(&schedule), invalid);
+    }
+
+    fn mint_with_default_state(state: AccountState) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::DefaultAccountState]).unwrap();
+        let mut data = vec![0; len];
+        let mut mint = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        mint.init_extension::<DefaultAccountState>(true).unwrap().state = state as u8;
+        mint.base = Mint { is_initialized: true, ..Mint::default() };
+        mint.pack_base();
+        mint.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn test_validate_mint_extensions() {
+        let unsupported: Result<(), ProgramError> = Err(VestingError::UnsupportedMintExtension.into());
+
+        let data = mint_with_default_state(AccountState::Initialized);
+        let mint = StateWithExtensions::<Mint>::unpack(&data).unwrap();
+        assert_eq!(validate_mint_extensions(&mint, &mint.get_extension_types().unwrap()), Ok(()));
+
+        // Frozen vault and recipient accounts could never move the tokens
+        let data = mint_with_default_state(AccountState::Frozen);
+        let mint = StateWithExtensions::<Mint>::unpack(&data).unwrap();
+        assert_eq!(validate_mint_extensions(&mint, &mint.get_extension_types().unwrap()), unsupported);
+
+        for extension in [
+            ExtensionType::TransferHook,
+            ExtensionType::PermanentDelegate,
+            ExtensionType::NonTransferable,
+        ] {
+            assert_eq!(validate_mint_extensions(&mint, &[extension]), unsupported);
+        }
+        assert_eq!(validate_mint_extensions(&mint, &[ExtensionType::TransferFeeConfig]), Ok(()));
+    }
+
+    #[test]
+    fn test_recipient_schedules_vest_independently() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.total_amount = 1_000.into();
+        vesting.start_time = 1_000.into();
+        vesting.schedule_count = 2;
+        vesting.schedules[0].vesting_period = 100.into();
+        vesting.schedules[1] = PodVestingSchedule::from(&VestingSchedule {
+            cliff_period: 50,
+            vesting_period: 200,
+            tge_basis_points: 1000,
+            ..VestingSchedule::default()
+        });
+        let mut recipients = inline_recipients(&[5000, 5000]).iter().map(Recipient::from).collect::<Vec<_>>();
+        recipients[1].schedule_index = 1;
+        let vested = |recipient: &Recipient, time| calculate_recipient_vested(&vesting, recipient, time).unwrap();
+
+        // Linear over 100 seconds against TGE, a 50-second cliff and 200 seconds
+        assert_eq!(vested(&recipients[0], 1_040), 200);
+        assert_eq!(vested(&recipients[1], 1_040), 50);
+        assert_eq!(vested(&recipients[0], 1_100), 500);
+        assert_eq!(vested(&recipients[1], 1_100), 200);
+        assert_eq!(vested(&recipients[1], 1_200), 500);
+
+        // An index past schedule_count names no schedule, even if the slot exists
+        recipients[1].schedule_index = 2;
+        assert_eq!(
+            calculate_recipient_vested(&vesting, &recipients[1], 1_100),
+            Err(VestingError::InvalidScheduleIndex.into())
+        );
+        assert!(vesting.schedule(MAX_SCHEDULES as u8).is_none());
+    }
+
+    #[test]
+    fn test_revoke_claws_back_unvested() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.total_amount = 1_000.into();
+        vesting.start_time = 1_000.into();
+        vesting.schedule_count = 1;
+        vesting.schedules[0].vesting_period = 100.into();
+        let mut recipients = inline_recipients(&[6000, 4000]);
+
+        // 40% of the 600-token allocation has vested at revocation
+        assert_eq!(revoke_recipient(&vesting, &mut recipients[0], 1_040).unwrap(), 360);
+        assert_eq!(i64::from(recipients[0].revoked_at), 1_040);
+        // Vesting stops at revocation time
+        let revoked = Recipient::from(&recipients[0]);
+        assert_eq!(calculate_recipient_vested(&vesting, &revoked, 5_000).unwrap(), 240);
+
+        // Before start nothing has vested, after the end nothing is clawed back
+        let mut unstarted = recipients[1];
+        assert_eq!(revoke_recipient(&vesting, &mut unstarted, 999).unwrap(), 400);
+        assert_eq!(revoke_recipient(&vesting, &mut recipients[1], 1_100).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_revoke_twice_fails() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.total_amount = 1_000.into();
+        vesting.schedule_count = 1;
+        vesting.schedules[0].vesting_period = 100.into();
+        let mut recipients = inline_recipients(&[10000]);
+
+        assert_eq!(revoke_recipient(&vesting, &mut recipients[0], 50).unwrap(), 500);
+        assert_eq!(
+            revoke_recipient(&vesting, &mut recipients[0], 60),
+            Err(VestingError::AlreadyRevoked.into())
+        );
+        // The first revocation time is kept
+        assert_eq!(i64::from(recipients[0].revoked_at), 50);
+    }
+
+    #[test]
+    fn test_rotate_to_duplicate_wallet_fails() {
+        let program_id = Pubkey::new_unique();
+        let vesting_key = Pubkey::new_unique();
+        let recipients = inline_recipients(&[5000, 5000]);
+        let (current, other) = (recipients[0].wallet, recipients[1].wallet);
+        let mut data = vesting_data(&VestingHeader::zeroed(), &recipients);
+        let (mut signer_lamports, mut vesting_lamports) = (0, 0);
+        let system_program = solana_program::system_program::ID;
+        let accounts = [
+            account(&current, true, &mut signer_lamports, &mut [], &system_program),
+            account(&vesting_key, false, &mut vesting_lamports, &mut data, &program_id),
+        ];
+
+        let rotate = |new_wallet| process_rotate_recipient_wallet(&program_id, &accounts, new_wallet);
+        assert_eq!(rotate(other), Err(VestingError::DuplicateRecipient.into()));
+        assert_eq!(rotate(current), Err(VestingError::DuplicateRecipient.into()));
+        assert_eq!(rotate(Pubkey::default()), Err(VestingError::InvalidRecipientWallet.into()));
+
+        let new_wallet = Pubkey::new_unique();
+        assert_eq!(rotate(new_wallet), Ok(()));
+        let vesting_data = accounts[1].data.borrow();
+        let (_, recipients) = VestingHeader::load(&vesting_data).unwrap();
+        assert_eq!(recipients[0].wallet, new_wallet);
+        assert_eq!(recipients[1].wallet, other);
+    }
+
+    #[test]
+    fn test_fund_rejects_wrong_funder_and_amount() {
+        let program_id = Pubkey::new_unique();
+        let funder = Pubkey::new_unique();
+        let (source_key, vault_key, vesting_key, mint_key) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (token_program, clock_id) = (spl_token::ID, solana_program::sysvar::clock::ID);
+        let mut vesting = VestingHeader::zeroed();
+        vesting.funder = funder;
+        vesting.expected_amount = 1_000.into();
+
+        let fund = |vesting: &VestingHeader, signer: &Pubkey, amount| {
+            let mut data = vesting_data(vesting, &inline_recipients(&[10000]));
+            let [l0, l1, l2, l3, l4, l5, l6] = &mut [0u64; 7];
+            let accounts = [
+                account(signer, true, l0, &mut [], &solana_program::system_program::ID),
+                account(&source_key, false, l1, &mut [], &token_program),
+                account(&vault_key, false, l2, &mut [], &token_program),
+                account(&vesting_key, false, l3, &mut data, &program_id),
+                account(&token_program, false, l4, &mut [], &solana_program::bpf_loader::ID),
+                account(&clock_id, false, l5, &mut [], &solana_program::sysvar::ID),
+                account(&mint_key, false, l6, &mut [], &token_program),
+            ];
+            process_fund(&program_id, &accounts, amount)
+        };
+
+        let unauthorized: Result<(), ProgramError> = Err(VestingError::UnauthorizedFunder.into());
+        let invalid_amount: Result<(), ProgramError> = Err(VestingError::InvalidAmount.into());
+        assert_eq!(fund(&vesting, &Pubkey::new_unique(), 1_000), unauthorized);
+        assert_eq!(fund(&vesting, &funder, 999), invalid_amount);
+        assert_eq!(fund(&vesting, &funder, 1_001), invalid_amount);
+        assert_eq!(fund(&vesting, &funder, 0), invalid_amount);
+
+        // Without an expected amount only the funder is checked
+        vesting.expected_amount = 0.into();
+        assert_eq!(fund(&vesting, &Pubkey::new_unique(), 999), unauthorized);
+    }
+
+    #[test]
+    fn test_validate_start_time() {
+        let invalid: Result<(), ProgramError> = Err(VestingError::InvalidStartTime.into());
+        let now = 1_700_000_000;
+
+        assert_eq!(validate_start_time(now + 1, now), Ok(()));
+        assert_eq!(validate_start_time(now + MAX_START_DELAY, now), Ok(()));
+        // Past and current times are rejected
+        assert_eq!(validate_start_time(now - 1, now), invalid);
+        assert_eq!(validate_start_time(now, now), invalid);
+        assert_eq!(validate_start_time(0, now), invalid);
+        assert_eq!(validate_start_time(now + MAX_START_DELAY + 1, now), invalid);
+    }
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_pause_is_capped_per_pause() {
+        let mut vesting = VestingHeader::zeroed();
+        let now = 1_700_000_000;
+
+        assert_eq!(pause_vesting(&mut vesting, now), Ok(()));
+        assert_eq!(i64::from(vesting.paused_until), now + 7 * DAY);
+        assert_eq!(i64::from(vesting.pause_duration_used), 7 * DAY);
+        assert!(vesting.is_paused(now + 7 * DAY - 1));
+        // The pause ends on its own and cannot be extended while running
+        assert!(!vesting.is_paused(now + 7 * DAY));
+        assert_eq!(pause_vesting(&mut vesting, now + DAY), Err(VestingError::VestingPaused.into()));
+    }
+
+    #[test]
+    fn test_pause_budget_is_bounded() {
+        let mut vesting = VestingHeader::zeroed();
+        let mut now = 1_700_000_000;
+
+        for _ in 0..4 {
+            assert_eq!(pause_vesting(&mut vesting, now), Ok(()));
+            now += 7 * DAY;
+        }
+        // The fifth pause only gets the two days left of the 30-day budget
+        assert_eq!(pause_vesting(&mut vesting, now), Ok(()));
+        assert_eq!(i64::from(vesting.paused_until), now + 2 * DAY);
+        assert_eq!(i64::from(vesting.pause_duration_used), 30 * DAY);
+        now += 2 * DAY;
+        assert_eq!(pause_vesting(&mut vesting, now), Err(VestingError::PauseBudgetExhausted.into()));
+    }
+
+    #[test]
+    fn test_unpause_refunds_unused_budget() {
+        let mut vesting = VestingHeader::zeroed();
+        let now = 1_700_000_000;
+
+        assert_eq!(unpause_vesting(&mut vesting, now), Err(VestingError::NotPaused.into()));
+        assert_eq!(pause_vesting(&mut vesting, now), Ok(()));
+        assert_eq!(unpause_vesting(&mut vesting, now + DAY), Ok(()));
+        assert_eq!(i64::from(vesting.paused_until), 0);
+        assert_eq!(i64::from(vesting.pause_duration_used), DAY);
+        assert!(!vesting.is_paused(now + DAY));
+
+        // The refunded six days are available to the next pause
+        assert_eq!(pause_vesting(&mut vesting, now + 2 * DAY), Ok(()));
+        assert_eq!(i64::from(vesting.pause_duration_used), 8 * DAY);
+    }
+
+    #[test]
+    fn test_pause_does_not_shift_vesting() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.total_amount = 1_000.into();
+        vesting.start_time = 1_000.into();
+        vesting.schedule_count = 1;
+        vesting.schedules[0].vesting_period = (10 * DAY).into();
+        let recipient = Recipient::from(&inline_recipients(&[10000])[0]);
+        let vested = |vesting: &VestingHeader, time| calculate_recipient_vested(vesting, &recipient, time).unwrap();
+
+        let before: Vec<u64> = (0..=10).map(|day| vested(&vesting, 1_000 + day * DAY)).collect();
+        assert_eq!(pause_vesting(&mut vesting, 1_000 + DAY), Ok(()));
+        assert_eq!(unpause_vesting(&mut vesting, 1_000 + 4 * DAY), Ok(()));
+
+        // Accrual runs on through the pause; only payouts were blocked
+        assert_eq!(i64::from(vesting.start_time), 1_000);
+        let after: Vec<u64> = (0..=10).map(|day| vested(&vesting, 1_000 + day * DAY)).collect();
+        assert_eq!(after, before);
+        assert_eq!(vested(&vesting, 1_000 + 4 * DAY), 400);
+        assert_eq!(vested(&vesting, 1_000 + 10 * DAY), 1_000);
+    }
+
+    fn merkle_node(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        let (first, second) = if a <= b { (a, b) } else { (b, a) };
+        hashv(&[&[1], &first, &second]).to_bytes()
+    }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        let wallets = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let leaves: Vec<[u8; 32]> = wallets.iter().zip([100, 200, 300, 400]).map(|(w, a)| merkle_leaf(w, a)).collect();
+        let left = merkle_node(leaves[0], leaves[1]);
+        let right = merkle_node(leaves[2], leaves[3]);
+        let root = merkle_node(left, right);
+
+        assert!(verify_merkle_proof(leaves[0], &[leaves[1], right], &root));
+        assert!(verify_merkle_proof(leaves[1], &[leaves[0], right], &root));
+        assert!(verify_merkle_proof(leaves[2], &[leaves[3], left], &root));
+        assert!(verify_merkle_proof(leaves[3], &[leaves[2], left], &root));
+
+        // Tampered allocation, wallet or sibling
+        assert!(!verify_merkle_proof(merkle_leaf(&wallets[0], 101), &[leaves[1], right], &root));
+        assert!(!verify_merkle_proof(merkle_leaf(&Pubkey::new_unique(), 100), &[leaves[1], right], &root));
+        assert!(!verify_merkle_proof(leaves[0], &[leaves[2], right], &root));
+        assert!(!verify_merkle_proof(leaves[0], &[leaves[1], left], &root));
+
+        // Proofs of the wrong length
+        assert!(!verify_merkle_proof(leaves[0], &[], &root));
+        assert!(!verify_merkle_proof(leaves[0], &[leaves[1]], &root));
+        assert!(!verify_merkle_proof(leaves[0], &[leaves[1], right, leaves[2]], &root));
+        assert!(!verify_merkle_proof(leaves[0], &[right, leaves[1]], &root));
+    }
+
+    #[test]
+    fn test_merkle_siblings_are_sorted() {
+        let a = merkle_leaf(&Pubkey::new_unique(), 1);
+        let b = merkle_leaf(&Pubkey::new_unique(), 2);
+        let (low, high) = if a < b { (a, b) } else { (b, a) };
+        let root = hashv(&[&[1], &low, &high]).to_bytes();
+
+        // Either leaf proves against the sorted pair; the unsorted hash is not a root
+        assert!(verify_merkle_proof(low, &[high], &root));
+        assert!(verify_merkle_proof(high, &[low], &root));
+        let unsorted = hashv(&[&[1], &high, &low]).to_bytes();
+        assert!(!verify_merkle_proof(low, &[high], &unsorted));
+        assert!(!verify_merkle_proof(high, &[low], &unsorted));
+
+        // A single-leaf tree has the leaf as its root
+        assert!(verify_merkle_proof(a, &[], &a));
+    }
+
+    #[test]
+    fn test_close_leaves_rounding_dust() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.total_amount = 10.into();
+        let mut recipients = inline_recipients(&[3333, 3333, 3334]);
+        assert_eq!(outstanding_inline_amount(&vesting, &recipients).unwrap(), 9);
+
+        for entry in recipients.iter_mut() {
+            let recipient = Recipient::from(&*entry);
+            entry.claimed_amount = calculate_recipient_entitlement(&vesting, &recipient).unwrap().into();
+        }
+
+        // Every share is paid out, yet the vault still holds the remainder
+        // that Close sends to the dust account
+        assert_eq!(outstanding_inline_amount(&vesting, &recipients).unwrap(), 0);
+        let claimed: u64 = recipients.iter().map(|r| u64::from(r.claimed_amount)).sum();
+        assert_eq!(u64::from(vesting.total_amount) - claimed, 1);
+    }
+
+    #[test]
+    fn test_close_checks_external_claim_totals() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.recipient_accounts = true.into();
+        vesting.allocated_basis_points = 10000.into();
+        vesting.settled_basis_points = 9000.into();
+        assert!(!external_claims_settled(&vesting));
+        vesting.settled_basis_points = 10000.into();
+        assert!(external_claims_settled(&vesting));
+
+        let mut vesting = VestingHeader::zeroed();
+        vesting.merkle_root = [1; 32];
+        vesting.merkle_total_allocation = 1_000.into();
+        vesting.merkle_claimed_amount = 999.into();
+        assert!(!external_claims_settled(&vesting));
+        vesting.merkle_claimed_amount = 1_000.into();
+        assert!(external_claims_settled(&vesting));
+    }
+
+    #[test]
+    fn test_close_requires_revoked_remainder_claimed() {
+        let mut vesting = VestingHeader::zeroed();
+        vesting.total_amount = 1_000.into();
+        vesting.schedule_count = 1;
+        vesting.schedules[0].vesting_period = 100.into();
+        let mut recipients = inline_recipients(&[5000, 5000]);
+        recipients[1].revoked_at = 50.into();
+
+        // The revoked recipient is only owed what had vested at revocation
+        assert_eq!(outstanding_inline_amount(&vesting, &recipients).unwrap(), 750);
+        recipients[0].claimed_amount = 500.into();
+        recipients[1].claimed_amount = 249.into();
+        assert_eq!(outstanding_inline_amount(&vesting, &recipients).unwrap(), 1);
+        recipients[1].claimed_amount = 250.into();
+        assert_eq!(outstanding_inline_amount(&vesting, &recipients).unwrap(), 0);
+    }
+}
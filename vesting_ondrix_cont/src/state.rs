@@ -1,11 +1,35 @@
+use bytemuck::{Pod, Zeroable};
 use solana_program::{
     program_error::ProgramError,
     pubkey::Pubkey,
     program_pack::{IsInitialized, Pack, Sealed},
 };
+use spl_pod::primitives::{PodBool, PodI64, PodU16, PodU64};
 
-pub const MAX_RECIPIENTS: usize = 10;
+/// Bounded by the u8 `recipient_count`; the account grows with the number of recipients
+pub const MAX_RECIPIENTS: usize = u8::MAX as usize;
 pub const BASIS_POINTS_TOTAL: u16 = 10000;  // ✅ CRITICAL FIX: 10000 = 100% for precision
+pub const MAX_UNLOCK_POINTS: usize = 16;
+pub const MAX_SCHEDULES: usize = 4;
+
+/// Layout version in the first account byte. The original 641-byte layout
+/// stores `is_initialized` (1) there; such accounts can be read but must be
+/// converted with the Migrate instruction before they are modified
+pub const VERSION_BASELINE: u8 = 1;
+pub const CURRENT_VERSION: u8 = 2;
+/// Space at the end of the header for future fields without a resize
+const HEADER_RESERVED_LEN: usize = 70;
+
+const HEADER_LEN: usize = std::mem::size_of::<VestingHeader>();
+const POD_RECIPIENT_LEN: usize = std::mem::size_of::<PodRecipient>();
+
+// Original layout: a 141-byte header and 10 fixed 50-byte recipient slots
+const BASELINE_LEN: usize = 641;
+const BASELINE_HEADER_LEN: usize = 141;
+const BASELINE_RECIPIENT_LEN: usize = 50;
+const BASELINE_MAX_RECIPIENTS: usize = 10;
+
+const RECIPIENT_LEN: usize = 59;
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Recipient {
@@ -13,9 +37,177 @@ pub struct Recipient {
     pub basis_points: u16,  // ✅ CRITICAL FIX: Use basis points (0-10000) for precision
     pub claimed_amount: u64,
     pub last_claim_time: i64,
+    /// Unix timestamp of revocation (0 = not revoked); vesting stops at this moment
+    pub revoked_at: i64,
+    /// Index of the schedule in `VestingAccount::schedules`
+    pub schedule_index: u8,
+}
+
+impl Recipient {
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Recipient {
+            wallet: Pubkey::new_from_array(
+                src[0..32].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            basis_points: u16::from_le_bytes(
+                src[32..34].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            claimed_amount: u64::from_le_bytes(
+                src[34..42].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            last_claim_time: i64::from_le_bytes(
+                src[42..50].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            revoked_at: i64::from_le_bytes(
+                src[50..58].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            schedule_index: src[58],
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0..32].copy_from_slice(self.wallet.as_ref());
+        dst[32..34].copy_from_slice(&self.basis_points.to_le_bytes());
+        dst[34..42].copy_from_slice(&self.claimed_amount.to_le_bytes());
+        dst[42..50].copy_from_slice(&self.last_claim_time.to_le_bytes());
+        dst[50..58].copy_from_slice(&self.revoked_at.to_le_bytes());
+        dst[58] = self.schedule_index;
+    }
 }
 
+/// Recipient PDA `["recipient", vesting, wallet]` of a vesting in
+/// `recipient_accounts` mode; a claim writes only to it
 #[derive(Debug, Clone, Copy, Default)]
+pub struct RecipientAccount {
+    pub is_initialized: bool,
+    /// Vesting the recipient belongs to
+    pub vesting: Pubkey,
+    pub recipient: Recipient,
+}
+
+impl Sealed for RecipientAccount {}
+
+impl IsInitialized for RecipientAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RecipientAccount {
+    const LEN: usize = 1 + 32 + RECIPIENT_LEN;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let is_initialized = src[0] != 0;
+        let vesting = Pubkey::new_from_array(
+            src[1..33].try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?
+        );
+        let recipient = Recipient::unpack_from_slice(&src[33..33 + RECIPIENT_LEN])?;
+
+        Ok(RecipientAccount {
+            is_initialized,
+            vesting,
+            recipient,
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        if dst.len() != Self::LEN {
+            panic!("Invalid RecipientAccount slice length");
+        }
+
+        dst[0] = if self.is_initialized { 1 } else { 0 };
+        dst[1..33].copy_from_slice(self.vesting.as_ref());
+        self.recipient.pack_into_slice(&mut dst[33..33 + RECIPIENT_LEN]);
+    }
+}
+
+/// Receipt `["receipt", vesting, wallet]` of a merkle vesting recipient;
+/// created on the first claim
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaimReceipt {
+    pub is_initialized: bool,
+    pub vesting: Pubkey,
+    pub wallet: Pubkey,
+    /// Allocation from the tree leaf, in tokens
+    pub allocation: u64,
+    pub claimed_amount: u64,
+    pub last_claim_time: i64,
+}
+
+impl Sealed for ClaimReceipt {}
+
+impl IsInitialized for ClaimReceipt {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ClaimReceipt {
+    const LEN: usize = 1 + 32 + 32 + 8 + 8 + 8;
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        Ok(ClaimReceipt {
+            is_initialized: src[0] != 0,
+            vesting: Pubkey::new_from_array(
+                src[1..33].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            wallet: Pubkey::new_from_array(
+                src[33..65].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            allocation: u64::from_le_bytes(
+                src[65..73].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            claimed_amount: u64::from_le_bytes(
+                src[73..81].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+            last_claim_time: i64::from_le_bytes(
+                src[81..89].try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?
+            ),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        if dst.len() != Self::LEN {
+            panic!("Invalid ClaimReceipt slice length");
+        }
+
+        dst[0] = if self.is_initialized { 1 } else { 0 };
+        dst[1..33].copy_from_slice(self.vesting.as_ref());
+        dst[33..65].copy_from_slice(self.wallet.as_ref());
+        dst[65..73].copy_from_slice(&self.allocation.to_le_bytes());
+        dst[73..81].copy_from_slice(&self.claimed_amount.to_le_bytes());
+        dst[81..89].copy_from_slice(&self.last_claim_time.to_le_bytes());
+    }
+}
+
+/// Piecewise schedule point: `cumulative_basis_points` are unlocked `offset`
+/// seconds after start_time; release is linear between points
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UnlockPoint {
+    pub offset: i64,
+    pub cumulative_basis_points: u16,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub struct VestingSchedule {
     /// Cliff период в секундах от start_time
     pub cliff_period: i64,
@@ -23,10 +215,27 @@ pub struct VestingSchedule {
     pub vesting_period: i64,
     /// Basis points выпуска в TGE (Token Generation Event) - 0-10000
     pub tge_basis_points: u16,  // ✅ CRITICAL FIX: Use basis points for precision
+    /// Number of piecewise points (0 = TGE + cliff + linear vesting)
+    pub unlock_point_count: u8,
+    pub unlock_points: [UnlockPoint; MAX_UNLOCK_POINTS],
+    /// Step length in seconds after the cliff (0 = continuous linear release)
+    pub step_period: i64,
+}
+
+impl VestingSchedule {
+    pub fn is_piecewise(&self) -> bool {
+        self.unlock_point_count > 0
+    }
+
+    pub fn active_unlock_points(&self) -> &[UnlockPoint] {
+        &self.unlock_points[..(self.unlock_point_count as usize).min(MAX_UNLOCK_POINTS)]
+    }
 }
 
 // ✅ Безопасная структура VestingAccount с дополнительными полями
 pub struct VestingAccount {
+    /// Layout version the account was read from; always written as `CURRENT_VERSION`
+    pub version: u8,
     /// Флаг инициализации аккаунта
     pub is_initialized: bool,
     /// Кошелек, который инициализировал вестинг
@@ -35,37 +244,185 @@ pub struct VestingAccount {
     pub mint: Pubkey,
     /// Токен аккаунт vault
     pub vault: Pubkey,
-    /// Unix timestamp когда начинается вестинг (устанавливается при фандинге)
+    /// Unix timestamp when vesting starts (set on initialization or on funding)
     pub start_time: i64,
     /// Общее количество токенов для вестинга
     pub total_amount: u64,
-    /// Конфигурация расписания вестинга
-    pub schedule: VestingSchedule,
-    /// Массив получателей
-    pub recipients: [Recipient; MAX_RECIPIENTS],
+    /// Vesting schedules; schedules[0] comes from the InitializeVesting fields
+    pub schedules: [VestingSchedule; MAX_SCHEDULES],
+    /// Number of schedules in use (at least 1)
+    pub schedule_count: u8,
+    /// Recipients; a variable-length section at the end of the account
+    pub recipients: Vec<Recipient>,
     /// Фактическое количество получателей
     pub recipient_count: u8,
     // ✅ REMOVED: is_revoked flag to ensure complete immutability
     // pub is_revoked: bool, // DELETED - no termination possible
+    // Revocation is only possible with an explicit `revocable` on InitializeVesting (see below)
     /// ✅ НОВОЕ: Флаг финализации (предотвращает изменения после фандинга)
     pub is_finalized: bool,
     /// ✅ НОВОЕ: Время последнего распределения (для cooldown)
     pub last_distribution_time: i64,
+    /// Any signer may distribute, not only the initializer
+    pub permissionless_distribution: bool,
+    /// Reward in lamports paid to the distributor from the fee pool PDA
+    pub keeper_reward_lamports: u64,
+    /// Time of the last keeper reward payment (for rate limiting)
+    pub last_keeper_reward_time: i64,
+    /// Set once at initialization; without it the vesting is irrevocable
+    pub revocable: bool,
+    /// Key that may revoke a recipient (only if `revocable`)
+    pub revoker: Pubkey,
+    /// Token account that receives unvested tokens on revocation
+    pub clawback_account: Pubkey,
+    /// Receives the rent lamports when the vesting is closed
+    pub rent_recipient: Pubkey,
+    /// The only wallet allowed to Fund
+    pub funder: Pubkey,
+    /// Expected funding amount (0 = any)
+    pub expected_amount: u64,
+    /// Recipients live in separate PDAs (`RecipientAccount`), not in `recipients`
+    pub recipient_accounts: bool,
+    /// Sum of basis points of the created recipient PDAs (`recipient_accounts` mode)
+    pub allocated_basis_points: u16,
+    /// Root of the merkle tree of (wallet, allocation) leaves; zeros = mode disabled
+    pub merkle_root: [u8; 32],
+    /// Sum of the tree allocations; Fund must cover it in full
+    pub merkle_total_allocation: u64,
+    /// Key that may pause payouts (none by default)
+    pub guardian: Pubkey,
+    /// Payouts are blocked until this time (0 = not paused)
+    pub paused_until: i64,
+    /// Total duration of all pauses; bounded by the pause budget
+    pub pause_duration_used: i64,
+    /// Sum of basis points of recipient PDAs that claimed their full entitlement
+    pub settled_basis_points: u16,
+    /// Tokens claimed through merkle receipts so far
+    pub merkle_claimed_amount: u64,
 }
 
-impl Sealed for VestingAccount {}
+impl VestingAccount {
+    /// Size of a current-version account with the given number of recipients
+    pub fn account_len(recipient_count: usize) -> usize {
+        HEADER_LEN + recipient_count * POD_RECIPIENT_LEN
+    }
 
-impl IsInitialized for VestingAccount {
-    fn is_initialized(&self) -> bool {
-        self.is_initialized
+    pub fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        match src.first() {
+            Some(&CURRENT_VERSION) => Self::unpack_current(src),
+            Some(_) if src.len() == BASELINE_LEN => Self::unpack_baseline(src),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
     }
-}
 
-impl Pack for VestingAccount {
-    const LEN: usize = 1 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 2 + 1 + 1 + 8 + (MAX_RECIPIENTS * 50);
+    /// Writes the whole account in the `CURRENT_VERSION` layout
+    pub fn pack_into_slice(&self, dst: &mut [u8]) {
+        if dst.len() != Self::account_len(self.recipients.len())
+            || self.recipients.len() != self.recipient_count as usize
+        {
+            panic!("Invalid VestingAccount slice length");
+        }
 
-    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() != Self::LEN {
+        let (header, recipients) = dst.split_at_mut(HEADER_LEN);
+        *bytemuck::from_bytes_mut(header) = self.to_header();
+        for (pod, recipient) in bytemuck::cast_slice_mut::<_, PodRecipient>(recipients)
+            .iter_mut()
+            .zip(self.recipients.iter())
+        {
+            *pod = PodRecipient::from(recipient);
+        }
+    }
+
+    /// Full copy of a current-version account for clients; handlers read the
+    /// data in place through `VestingHeader::load`
+    fn unpack_current(src: &[u8]) -> Result<Self, ProgramError> {
+        let (header, recipients) = VestingHeader::load(src)?;
+
+        let mut schedules = [VestingSchedule::default(); MAX_SCHEDULES];
+        for (schedule, pod) in schedules.iter_mut().zip(header.schedules.iter()) {
+            *schedule = VestingSchedule::from(pod);
+        }
+
+        Ok(VestingAccount {
+            version: CURRENT_VERSION,
+            is_initialized: header.is_initialized.into(),
+            initializer: header.initializer,
+            mint: header.mint,
+            vault: header.vault,
+            start_time: header.start_time.into(),
+            total_amount: header.total_amount.into(),
+            schedules,
+            schedule_count: header.schedule_count,
+            recipients: recipients.iter().map(Recipient::from).collect(),
+            recipient_count: header.recipient_count,
+            is_finalized: header.is_finalized.into(),
+            last_distribution_time: header.last_distribution_time.into(),
+            permissionless_distribution: header.permissionless_distribution.into(),
+            keeper_reward_lamports: header.keeper_reward_lamports.into(),
+            last_keeper_reward_time: header.last_keeper_reward_time.into(),
+            revocable: header.revocable.into(),
+            revoker: header.revoker,
+            clawback_account: header.clawback_account,
+            rent_recipient: header.rent_recipient,
+            funder: header.funder,
+            expected_amount: header.expected_amount.into(),
+            recipient_accounts: header.recipient_accounts.into(),
+            allocated_basis_points: header.allocated_basis_points.into(),
+            merkle_root: header.merkle_root,
+            merkle_total_allocation: header.merkle_total_allocation.into(),
+            guardian: header.guardian,
+            paused_until: header.paused_until.into(),
+            pause_duration_used: header.pause_duration_used.into(),
+            settled_basis_points: header.settled_basis_points.into(),
+            merkle_claimed_amount: header.merkle_claimed_amount.into(),
+        })
+    }
+
+    fn to_header(&self) -> VestingHeader {
+        let mut schedules = [PodVestingSchedule::default(); MAX_SCHEDULES];
+        for (pod, schedule) in schedules.iter_mut().zip(self.schedules.iter()) {
+            *pod = PodVestingSchedule::from(schedule);
+        }
+
+        VestingHeader {
+            version: CURRENT_VERSION,
+            is_initialized: self.is_initialized.into(),
+            initializer: self.initializer,
+            mint: self.mint,
+            vault: self.vault,
+            start_time: self.start_time.into(),
+            total_amount: self.total_amount.into(),
+            recipient_count: self.recipient_count,
+            is_finalized: self.is_finalized.into(),
+            last_distribution_time: self.last_distribution_time.into(),
+            permissionless_distribution: self.permissionless_distribution.into(),
+            keeper_reward_lamports: self.keeper_reward_lamports.into(),
+            revocable: self.revocable.into(),
+            revoker: self.revoker,
+            clawback_account: self.clawback_account,
+            rent_recipient: self.rent_recipient,
+            funder: self.funder,
+            expected_amount: self.expected_amount.into(),
+            schedule_count: self.schedule_count,
+            schedules,
+            recipient_accounts: self.recipient_accounts.into(),
+            allocated_basis_points: self.allocated_basis_points.into(),
+            merkle_root: self.merkle_root,
+            merkle_total_allocation: self.merkle_total_allocation.into(),
+            guardian: self.guardian,
+            paused_until: self.paused_until.into(),
+            pause_duration_used: self.pause_duration_used.into(),
+            last_keeper_reward_time: self.last_keeper_reward_time.into(),
+            settled_basis_points: self.settled_basis_points.into(),
+            merkle_claimed_amount: self.merkle_claimed_amount.into(),
+            reserved: [0; HEADER_RESERVED_LEN],
+        }
+    }
+
+    /// Original unversioned layout: only the v1 header fields and 10 recipient
+    /// slots; every setting added later gets its default value
+    fn unpack_baseline(src: &[u8]) -> Result<Self, ProgramError> {
+        if src.len() != BASELINE_LEN {
             return Err(ProgramError::InvalidAccountData);
         }
 
@@ -111,6 +468,9 @@ impl Pack for VestingAccount {
                 .map_err(|_| ProgramError::InvalidAccountData)?
         );
         let recipient_count = src[131];
+        if recipient_count as usize > BASELINE_MAX_RECIPIENTS {
+            return Err(ProgramError::InvalidAccountData);
+        }
         
         let is_finalized = src[132] != 0;
         let last_distribution_time = i64::from_le_bytes(
@@ -118,85 +478,450 @@ impl Pack for VestingAccount {
                 .map_err(|_| ProgramError::InvalidAccountData)?
         );
 
-        let mut recipients = [Recipient::default(); MAX_RECIPIENTS];
-        let mut offset = 141; 
-        
-        for (i, recipient) in recipients.iter_mut().enumerate() {
-            let wallet = Pubkey::new_from_array(
-                src[offset..offset + 32].try_into()
-                    .map_err(|_| ProgramError::InvalidAccountData)?
-            );
-            let basis_points = u16::from_le_bytes(
-                src[offset + 32..offset + 34].try_into()
-                    .map_err(|_| ProgramError::InvalidAccountData)?
-            );
-            let claimed_amount = u64::from_le_bytes(
-                src[offset + 34..offset + 42].try_into()
-                    .map_err(|_| ProgramError::InvalidAccountData)?
-            );
-            let last_claim_time = i64::from_le_bytes(
-                src[offset + 42..offset + 50].try_into()
-                    .map_err(|_| ProgramError::InvalidAccountData)?
-            );
-
-            if i < recipient_count as usize {
-                *recipient = Recipient {
-                    wallet,
-                    basis_points,
-                    claimed_amount,
-                    last_claim_time,
-                };
-            } else {
-                *recipient = Recipient::default();
-            }
-            offset += 50;
+        let mut recipients = Vec::with_capacity(recipient_count as usize);
+        let mut offset = BASELINE_HEADER_LEN;
+        for _ in 0..recipient_count {
+            recipients.push(Recipient {
+                wallet: Pubkey::new_from_array(
+                    src[offset..offset + 32].try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?
+                ),
+                basis_points: u16::from_le_bytes(
+                    src[offset + 32..offset + 34].try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?
+                ),
+                claimed_amount: u64::from_le_bytes(
+                    src[offset + 34..offset + 42].try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?
+                ),
+                last_claim_time: i64::from_le_bytes(
+                    src[offset + 42..offset + 50].try_into()
+                        .map_err(|_| ProgramError::InvalidAccountData)?
+                ),
+                ..Recipient::default()
+            });
+            offset += BASELINE_RECIPIENT_LEN;
         }
 
+        let mut schedules = [VestingSchedule::default(); MAX_SCHEDULES];
+        schedules[0] = VestingSchedule {
+            cliff_period,
+            vesting_period,
+            tge_basis_points,
+            ..VestingSchedule::default()
+        };
+
         Ok(VestingAccount {
+            version: VERSION_BASELINE,
             is_initialized,
             initializer,
             mint,
             vault,
             start_time,
             total_amount,
-            schedule: VestingSchedule {
-                cliff_period,
-                vesting_period,
-                tge_basis_points,
-            },
+            schedules,
+            schedule_count: 1,
             recipients,
             recipient_count,
             is_finalized,
             last_distribution_time,
+            permissionless_distribution: false,
+            keeper_reward_lamports: 0,
+            last_keeper_reward_time: 0,
+            revocable: false,
+            revoker: Pubkey::default(),
+            clawback_account: Pubkey::default(),
+            rent_recipient: initializer,
+            funder: initializer,
+            expected_amount: 0,
+            recipient_accounts: false,
+            allocated_basis_points: 0,
+            merkle_root: [0; 32],
+            merkle_total_allocation: 0,
+            guardian: Pubkey::default(),
+            paused_until: 0,
+            pause_duration_used: 0,
+            settled_basis_points: 0,
+            merkle_claimed_amount: 0,
         })
     }
+}
 
-    fn pack_into_slice(&self, dst: &mut [u8]) {
-        if dst.len() != Self::LEN {
-            panic!("Invalid VestingAccount slice length");
+impl IsInitialized for VestingAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+/// Zero-copy vesting header; every field has alignment 1, so the header
+/// is read directly from the account data
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct VestingHeader {
+    /// Always `CURRENT_VERSION`
+    pub version: u8,
+    pub is_initialized: PodBool,
+    pub initializer: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub start_time: PodI64,
+    pub total_amount: PodU64,
+    pub recipient_count: u8,
+    pub is_finalized: PodBool,
+    pub last_distribution_time: PodI64,
+    pub permissionless_distribution: PodBool,
+    pub keeper_reward_lamports: PodU64,
+    pub revocable: PodBool,
+    pub revoker: Pubkey,
+    pub clawback_account: Pubkey,
+    pub rent_recipient: Pubkey,
+    pub funder: Pubkey,
+    pub expected_amount: PodU64,
+    pub schedule_count: u8,
+    pub schedules: [PodVestingSchedule; MAX_SCHEDULES],
+    pub recipient_accounts: PodBool,
+    pub allocated_basis_points: PodU16,
+    pub merkle_root: [u8; 32],
+    pub merkle_total_allocation: PodU64,
+    pub guardian: Pubkey,
+    pub paused_until: PodI64,
+    pub pause_duration_used: PodI64,
+    pub last_keeper_reward_time: PodI64,
+    pub settled_basis_points: PodU16,
+    pub merkle_claimed_amount: PodU64,
+    pub reserved: [u8; HEADER_RESERVED_LEN],
+}
+
+impl VestingHeader {
+    /// Header and recipient section of a current-version account, borrowed
+    /// from the account data without copying
+    pub fn load(data: &[u8]) -> Result<(&Self, &[PodRecipient]), ProgramError> {
+        let (header, recipients) = Self::split(data)?;
+        Ok((bytemuck::from_bytes(header), bytemuck::cast_slice(recipients)))
+    }
+
+    /// Like `load`, but writable: changes land in the account directly, and a
+    /// handler writes only the fields and recipient entries it changes
+    pub fn load_mut(data: &mut [u8]) -> Result<(&mut Self, &mut [PodRecipient]), ProgramError> {
+        Self::split(data)?;
+        let (header, recipients) = data.split_at_mut(HEADER_LEN);
+        Ok((bytemuck::from_bytes_mut(header), bytemuck::cast_slice_mut(recipients)))
+    }
+
+    fn split(data: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+        if data.len() < HEADER_LEN || data[0] != CURRENT_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let (header, recipients) = data.split_at(HEADER_LEN);
+        let header: &VestingHeader = bytemuck::from_bytes(header);
+        if data.len() != VestingAccount::account_len(header.recipient_count as usize) {
+            return Err(ProgramError::InvalidAccountData);
         }
+        Ok((bytemuck::bytes_of(header), recipients))
+    }
 
-        dst[0] = if self.is_initialized { 1 } else { 0 };
-        dst[1..33].copy_from_slice(self.initializer.as_ref());
-        dst[33..65].copy_from_slice(self.mint.as_ref());
-        dst[65..97].copy_from_slice(self.vault.as_ref());
-        dst[97..105].copy_from_slice(&self.start_time.to_le_bytes());
-        dst[105..113].copy_from_slice(&self.total_amount.to_le_bytes());
-        dst[113..121].copy_from_slice(&self.schedule.cliff_period.to_le_bytes());
-        dst[121..129].copy_from_slice(&self.schedule.vesting_period.to_le_bytes());
-        dst[129..131].copy_from_slice(&self.schedule.tge_basis_points.to_le_bytes());
-        dst[131] = self.recipient_count;
-        
-        dst[132] = if self.is_finalized { 1 } else { 0 };
-        dst[133..141].copy_from_slice(&self.last_distribution_time.to_le_bytes());
+    /// Appends `recipients` to the end of the recipient section. The account
+    /// must already be resized for the new entries
+    pub fn append_recipients(data: &mut [u8], recipients: &[Recipient]) -> Result<(), ProgramError> {
+        if data.len() < HEADER_LEN || data[0] != CURRENT_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let data_len = data.len();
+        let header: &mut VestingHeader = bytemuck::from_bytes_mut(&mut data[..HEADER_LEN]);
+        let first_new = header.recipient_count as usize;
+        let recipient_count = first_new + recipients.len();
+        if recipient_count > MAX_RECIPIENTS
+            || data_len != VestingAccount::account_len(recipient_count)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        header.recipient_count = recipient_count as u8;
 
-        let mut offset = 141; 
-        for recipient in &self.recipients {
-            dst[offset..offset + 32].copy_from_slice(recipient.wallet.as_ref());
-            dst[offset + 32..offset + 34].copy_from_slice(&recipient.basis_points.to_le_bytes());
-            dst[offset + 34..offset + 42].copy_from_slice(&recipient.claimed_amount.to_le_bytes());
-            dst[offset + 42..offset + 50].copy_from_slice(&recipient.last_claim_time.to_le_bytes());
-            offset += 50; 
+        let (_, section) = Self::load_mut(data)?;
+        for (pod, recipient) in section[first_new..].iter_mut().zip(recipients.iter()) {
+            *pod = PodRecipient::from(recipient);
         }
+        Ok(())
     }
-}
\ No newline at end of file
+
+    pub fn is_initialized(&self) -> bool {
+        self.is_initialized.into()
+    }
+
+    pub fn is_merkle(&self) -> bool {
+        self.merkle_root != [0; 32]
+    }
+
+    /// A pause expires on its own, without Unpause
+    pub fn is_paused(&self, current_time: i64) -> bool {
+        i64::from(self.paused_until) > current_time
+    }
+
+    /// Recipients are stored in the account section, not in PDAs or a merkle tree
+    pub fn uses_inline_recipients(&self) -> bool {
+        !bool::from(self.recipient_accounts) && !self.is_merkle()
+    }
+
+    /// Schedule a recipient with schedule index `index` vests on; `None` past
+    /// `schedule_count`
+    pub fn schedule(&self, index: u8) -> Option<VestingSchedule> {
+        self.schedules[..(self.schedule_count as usize).min(MAX_SCHEDULES)]
+            .get(index as usize)
+            .map(VestingSchedule::from)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+pub struct PodUnlockPoint {
+    pub offset: PodI64,
+    pub cumulative_basis_points: PodU16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
+pub struct PodVestingSchedule {
+    pub cliff_period: PodI64,
+    pub vesting_period: PodI64,
+    pub tge_basis_points: PodU16,
+    pub step_period: PodI64,
+    pub unlock_point_count: u8,
+    pub unlock_points: [PodUnlockPoint; MAX_UNLOCK_POINTS],
+}
+
+/// Recipient entry in the section after `VestingHeader`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct PodRecipient {
+    pub wallet: Pubkey,
+    pub basis_points: PodU16,
+    pub claimed_amount: PodU64,
+    pub last_claim_time: PodI64,
+    pub revoked_at: PodI64,
+    pub schedule_index: u8,
+}
+
+impl From<&PodRecipient> for Recipient {
+    fn from(pod: &PodRecipient) -> Self {
+        Recipient {
+            wallet: pod.wallet,
+            basis_points: pod.basis_points.into(),
+            claimed_amount: pod.claimed_amount.into(),
+            last_claim_time: pod.last_claim_time.into(),
+            revoked_at: pod.revoked_at.into(),
+            schedule_index: pod.schedule_index,
+        }
+    }
+}
+
+impl From<&Recipient> for PodRecipient {
+    fn from(recipient: &Recipient) -> Self {
+        PodRecipient {
+            wallet: recipient.wallet,
+            basis_points: recipient.basis_points.into(),
+            claimed_amount: recipient.claimed_amount.into(),
+            last_claim_time: recipient.last_claim_time.into(),
+            revoked_at: recipient.revoked_at.into(),
+            schedule_index: recipient.schedule_index,
+        }
+    }
+}
+
+impl From<&PodVestingSchedule> for VestingSchedule {
+    fn from(pod: &PodVestingSchedule) -> Self {
+        let mut unlock_points = [UnlockPoint::default(); MAX_UNLOCK_POINTS];
+        for (point, pod_point) in unlock_points.iter_mut().zip(pod.unlock_points.iter()) {
+            *point = UnlockPoint {
+                offset: pod_point.offset.into(),
+                cumulative_basis_points: pod_point.cumulative_basis_points.into(),
+            };
+        }
+
+        VestingSchedule {
+            cliff_period: pod.cliff_period.into(),
+            vesting_period: pod.vesting_period.into(),
+            tge_basis_points: pod.tge_basis_points.into(),
+            unlock_point_count: pod.unlock_point_count,
+            unlock_points,
+            step_period: pod.step_period.into(),
+        }
+    }
+}
+
+impl From<&VestingSchedule> for PodVestingSchedule {
+    fn from(schedule: &VestingSchedule) -> Self {
+        let mut unlock_points = [PodUnlockPoint::default(); MAX_UNLOCK_POINTS];
+        for (pod_point, point) in unlock_points.iter_mut().zip(schedule.unlock_points.iter()) {
+            *pod_point = PodUnlockPoint {
+                offset: point.offset.into(),
+                cumulative_basis_points: point.cumulative_basis_points.into(),
+            };
+        }
+
+        PodVestingSchedule {
+            cliff_period: schedule.cliff_period.into(),
+            vesting_period: schedule.vesting_period.into(),
+            tge_basis_points: schedule.tge_basis_points.into(),
+            step_period: schedule.step_period.into(),
+            unlock_point_count: schedule.unlock_point_count,
+            unlock_points,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A funded 641-byte account as written by the original program, with
+    /// `recipients` in the first slots and the remaining slots zeroed.
+    fn baseline_account(recipients: &[(Pubkey, u16, u64, i64)]) -> Vec<u8> {
+        let mut data = vec![0u8; BASELINE_LEN];
+        data[0] = 1;
+        data[1..33].copy_from_slice(Pubkey::new_unique().as_ref());
+        data[33..65].copy_from_slice(Pubkey::new_unique().as_ref());
+        data[65..97].copy_from_slice(Pubkey::new_unique().as_ref());
+        data[97..105].copy_from_slice(&1_700_000_000i64.to_le_bytes());
+        data[105..113].copy_from_slice(&1_000_000u64.to_le_bytes());
+        data[113..121].copy_from_slice(&300i64.to_le_bytes());
+        data[121..129].copy_from_slice(&3600i64.to_le_bytes());
+        data[129..131].copy_from_slice(&1000u16.to_le_bytes());
+        data[131] = recipients.len() as u8;
+        data[132] = 1;
+        data[133..141].copy_from_slice(&1_700_000_900i64.to_le_bytes());
+
+        let mut offset = BASELINE_HEADER_LEN;
+        for (wallet, basis_points, claimed_amount, last_claim_time) in recipients {
+            data[offset..offset + 32].copy_from_slice(wallet.as_ref());
+            data[offset + 32..offset + 34].copy_from_slice(&basis_points.to_le_bytes());
+            data[offset + 34..offset + 42].copy_from_slice(&claimed_amount.to_le_bytes());
+            data[offset + 42..offset + 50].copy_from_slice(&last_claim_time.to_le_bytes());
+            offset += BASELINE_RECIPIENT_LEN;
+        }
+        data
+    }
+
+    #[test]
+    fn test_unpack_baseline_account() {
+        let recipients = [
+            (Pubkey::new_unique(), 7000, 250_000, 1_700_000_600),
+            (Pubkey::new_unique(), 3000, 107_142, 1_700_000_900),
+        ];
+        let data = baseline_account(&recipients);
+
+        let vesting = VestingAccount::unpack_from_slice(&data).unwrap();
+        assert_eq!(vesting.version, VERSION_BASELINE);
+        assert!(vesting.is_initialized);
+        assert!(vesting.is_finalized);
+        assert_eq!(vesting.initializer.as_ref(), &data[1..33]);
+        assert_eq!(vesting.start_time, 1_700_000_000);
+        assert_eq!(vesting.total_amount, 1_000_000);
+        assert_eq!(vesting.last_distribution_time, 1_700_000_900);
+        assert_eq!(vesting.schedule_count, 1);
+        assert_eq!(vesting.schedules[0].cliff_period, 300);
+        assert_eq!(vesting.schedules[0].vesting_period, 3600);
+        assert_eq!(vesting.schedules[0].tge_basis_points, 1000);
+        assert!(!vesting.schedules[0].is_piecewise());
+        assert_eq!(vesting.rent_recipient, vesting.initializer);
+        assert_eq!(vesting.funder, vesting.initializer);
+        assert!(!vesting.permissionless_distribution);
+        assert!(!vesting.revocable);
+        assert!(!vesting.recipient_accounts);
+        assert_eq!(vesting.merkle_root, [0; 32]);
+
+        assert_eq!(vesting.recipients.len(), 2);
+        for (recipient, (wallet, basis_points, claimed_amount, last_claim_time)) in
+            vesting.recipients.iter().zip(recipients.iter())
+        {
+            assert_eq!(recipient.wallet, *wallet);
+            assert_eq!(recipient.basis_points, *basis_points);
+            assert_eq!(recipient.claimed_amount, *claimed_amount);
+            assert_eq!(recipient.last_claim_time, *last_claim_time);
+            assert_eq!(recipient.revoked_at, 0);
+            assert_eq!(recipient.schedule_index, 0);
+        }
+    }
+
+    #[test]
+    fn test_unpack_rejects_unknown_layout() {
+        let data = baseline_account(&[(Pubkey::new_unique(), 10000, 0, 0)]);
+        assert!(VestingAccount::unpack_from_slice(&data[..BASELINE_LEN - 1]).is_err());
+
+        let mut too_many = data.clone();
+        too_many[131] = BASELINE_MAX_RECIPIENTS as u8 + 1;
+        assert!(VestingAccount::unpack_from_slice(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_migrate_repack_keeps_recipient_balances() {
+        let recipients: Vec<_> = (0..BASELINE_MAX_RECIPIENTS as u64)
+            .map(|i| (Pubkey::new_unique(), 1000, 10_000 + i, 1_700_000_000 + i as i64))
+            .collect();
+        let baseline = VestingAccount::unpack_from_slice(&baseline_account(&recipients)).unwrap();
+
+        // Same steps as the Migrate instruction: grow the account and repack
+        let mut data = vec![0u8; VestingAccount::account_len(baseline.recipients.len())];
+        baseline.pack_into_slice(&mut data);
+        assert_eq!(data[0], CURRENT_VERSION);
+
+        let migrated = VestingAccount::unpack_from_slice(&data).unwrap();
+        assert_eq!(migrated.version, CURRENT_VERSION);
+        assert_eq!(migrated.total_amount, baseline.total_amount);
+        assert_eq!(migrated.start_time, baseline.start_time);
+        assert_eq!(migrated.last_distribution_time, baseline.last_distribution_time);
+        assert_eq!(migrated.schedules[0], baseline.schedules[0]);
+        assert_eq!(migrated.recipient_count as usize, recipients.len());
+        for (recipient, (wallet, basis_points, claimed_amount, last_claim_time)) in
+            migrated.recipients.iter().zip(recipients.iter())
+        {
+            assert_eq!(recipient.wallet, *wallet);
+            assert_eq!(recipient.basis_points, *basis_points);
+            assert_eq!(recipient.claimed_amount, *claimed_amount);
+            assert_eq!(recipient.last_claim_time, *last_claim_time);
+        }
+    }
+
+    #[test]
+    fn test_append_recipients_to_existing_account() {
+        let existing = Recipient {
+            wallet: Pubkey::new_unique(),
+            basis_points: 4000,
+            claimed_amount: 123,
+            ..Recipient::default()
+        };
+        let mut data = vec![0u8; VestingAccount::account_len(1)];
+        let (_, section) = data.split_at_mut(HEADER_LEN);
+        section.copy_from_slice(bytemuck::bytes_of(&PodRecipient::from(&existing)));
+        let header: &mut VestingHeader = bytemuck::from_bytes_mut(&mut data[..HEADER_LEN]);
+        header.version = CURRENT_VERSION;
+        header.recipient_count = 1;
+
+        // The account is resized first, as in AddRecipients
+        let added = [
+            Recipient { wallet: Pubkey::new_unique(), basis_points: 3500, schedule_index: 1, ..Recipient::default() },
+            Recipient { wallet: Pubkey::new_unique(), basis_points: 2500, ..Recipient::default() },
+        ];
+        assert!(VestingHeader::append_recipients(&mut data, &added).is_err());
+        data.resize(VestingAccount::account_len(3), 0);
+        VestingHeader::append_recipients(&mut data, &added).unwrap();
+
+        let (header, recipients) = VestingHeader::load_mut(&mut data).unwrap();
+        assert_eq!(header.recipient_count, 3);
+        assert_eq!(recipients.len(), 3);
+        assert_eq!(recipients[0].wallet, existing.wallet);
+        assert_eq!(u64::from(recipients[0].claimed_amount), 123);
+        for (pod, recipient) in recipients[1..].iter().zip(added.iter()) {
+            assert_eq!(pod.wallet, recipient.wallet);
+            assert_eq!(u16::from(pod.basis_points), recipient.basis_points);
+            assert_eq!(pod.schedule_index, recipient.schedule_index);
+        }
+
+        // Writes through `load_mut` land in the account bytes
+        recipients[2].claimed_amount = 77.into();
+        let offset = HEADER_LEN + 2 * POD_RECIPIENT_LEN;
+        assert_eq!(&data[offset..offset + 32], added[1].wallet.as_ref());
+        assert_eq!(&data[offset + 34..offset + 42], &77u64.to_le_bytes());
+        assert_eq!(data[offset + RECIPIENT_LEN - 1], 0);
+        assert_eq!(data[HEADER_LEN + POD_RECIPIENT_LEN + RECIPIENT_LEN - 1], 1);
+    }
+}
@@ -44,6 +44,7 @@ function createFundInstruction(
     sourceTokenAccount,
     vaultPDA,
     vestingPDA,
+    mint,
     amount
 ) {
     // Создаем данные инструкции: 1 байт (инструкция) + 8 байт (amount)
@@ -64,6 +65,7 @@ function createFundInstruction(
             { pubkey: vestingPDA, isSigner: false, isWritable: true },              // 3. Vesting PDA
             { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },       // 4. Token Program
             { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },    // 5. Clock Sysvar
+            { pubkey: mint, isSigner: false, isWritable: false },                   // 6. Token Mint
         ],
         data,
     });
@@ -148,6 +150,7 @@ async function fundVesting() {
             sourceTokenAccount,
             vaultPDA,
             vestingPDA,
+            mint,
             FUNDING_CONFIG.AMOUNT
         );
         
@@ -39,6 +39,7 @@ function createClaimInstruction(
     vestingPDA,
     vaultPDA,
     vaultAuthority,
+    mint,
     recipientATAs
 ) {
     // Создаем данные инструкции: только 1 байт (инструкция)
@@ -55,9 +56,10 @@ function createClaimInstruction(
         { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },  // 3. Token Program
         { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false }, // 4. Clock Sysvar
         { pubkey: vaultAuthority, isSigner: false, isWritable: false },    // 5. Vault Authority PDA
+        { pubkey: mint, isSigner: false, isWritable: false },              // 6. Token Mint
     ];
     
-    // Добавляем ATA получателей (6+)
+    // Добавляем ATA получателей (7+)
     for (const ata of recipientATAs) {
         keys.push({ pubkey: ata, isSigner: false, isWritable: true });
     }
@@ -167,6 +169,7 @@ async function claimTokens() {
             vestingPDA,
             vaultPDA,
             vaultAuthority,
+            mint,
             recipientATAs
         );
         